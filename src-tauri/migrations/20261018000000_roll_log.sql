@@ -0,0 +1,16 @@
+-- Copyright (c) 2025 Jonathon B. Cobb
+-- Licensed under the MIT License
+
+-- Roll log (history of dice rolls made during the campaign)
+-- Individual dice are stored as a JSON array of die rolls.
+CREATE TABLE IF NOT EXISTS RollLog (
+    id INTEGER PRIMARY KEY,
+    timestamp TEXT NOT NULL,
+    expression TEXT NOT NULL,
+    value INTEGER NOT NULL,
+    dice TEXT NOT NULL,
+    label TEXT,
+    roller TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_RollLog_roller ON RollLog(roller);
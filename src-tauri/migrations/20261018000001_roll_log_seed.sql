@@ -0,0 +1,6 @@
+-- Copyright (c) 2025 Jonathon B. Cobb
+-- Licensed under the MIT License
+
+-- Record the seed and stream of each roll so that it can be replayed.
+ALTER TABLE RollLog ADD COLUMN seed INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE RollLog ADD COLUMN stream INTEGER NOT NULL DEFAULT 0;
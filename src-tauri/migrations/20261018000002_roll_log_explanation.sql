@@ -0,0 +1,5 @@
+-- Copyright (c) 2025 Jonathon B. Cobb
+-- Licensed under the MIT License
+
+-- Record a step-by-step explanation of each roll.
+ALTER TABLE RollLog ADD COLUMN explanation TEXT NOT NULL DEFAULT '';
@@ -0,0 +1,7 @@
+-- Copyright (c) 2025 Jonathon B. Cobb
+-- Licensed under the MIT License
+
+-- Track the hit points of player characters.
+ALTER TABLE Player ADD COLUMN hp INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE Player ADD COLUMN temp_hp INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE Player ADD COLUMN max_hp INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,10 @@
+-- Copyright (c) 2025 Jonathon B. Cobb
+-- Licensed under the MIT License
+
+-- Record everything needed to replay a roll exactly: whether it was rolled as
+-- a critical hit, the campaign's rule for critical hits at the time, who it
+-- was rolled for and the values of the variables it used.
+ALTER TABLE RollLog ADD COLUMN critical INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE RollLog ADD COLUMN critical_rule TEXT NOT NULL DEFAULT '"double_dice"';
+ALTER TABLE RollLog ADD COLUMN participant INTEGER;
+ALTER TABLE RollLog ADD COLUMN variables TEXT NOT NULL DEFAULT '{}';
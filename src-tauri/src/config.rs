@@ -16,8 +16,9 @@ mod paths;
 
 pub use app_settings::{AppSettings, SavepointSettings, SavepointTrigger, WindowSettings};
 pub use campaign_settings::{
-    CampaignSettings, FontSize, MonsterHitPoints, RulesSettings, RulesVersion, Theme, ThemeMode,
-    UiSettings, CURRENT_SCHEMA_VERSION,
+    CampaignSettings, ConcentrationChecks, CriticalHits, DeathSaveRolls, FontSize,
+    MonsterHitPoints, RulesSettings, RulesVersion, Theme, ThemeMode, UiSettings,
+    CURRENT_SCHEMA_VERSION,
 };
 pub use error::ConfigError;
 pub use paths::AppPaths;
@@ -51,6 +51,18 @@ pub struct RulesSettings {
     /// How monster hit points are determined.
     #[serde(default)]
     pub monster_hp: MonsterHitPoints,
+
+    /// How the extra dice of critical hits are rolled.
+    #[serde(default)]
+    pub critical_hits: CriticalHits,
+
+    /// How death saving throws are made.
+    #[serde(default)]
+    pub death_saves: DeathSaveRolls,
+
+    /// How concentration checks are made.
+    #[serde(default)]
+    pub concentration_checks: ConcentrationChecks,
 }
 
 /// UI preferences that can vary per campaign for different "feels".
@@ -96,6 +108,42 @@ pub enum MonsterHitPoints {
     Rolled,
 }
 
+/// How the extra dice of a critical hit are rolled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CriticalHits {
+    /// Roll twice as many damage dice.
+    #[default]
+    DoubleDice,
+
+    /// Roll the damage dice and add their maximum roll.
+    MaxPlusRoll,
+}
+
+/// How the death saving throws of dying players are made.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeathSaveRolls {
+    /// Roll automatically at the start of the player's turn.
+    #[default]
+    Rolled,
+
+    /// Let the player roll and record the result by hand.
+    Manual,
+}
+
+/// How the Constitution saving throw to keep concentrating after taking damage is made.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConcentrationChecks {
+    /// Roll automatically when the damage is applied.
+    #[default]
+    Rolled,
+
+    /// Report the DC and let the save be rolled by hand.
+    Manual,
+}
+
 /// Available color themes.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -186,6 +234,9 @@ impl Default for RulesSettings {
         Self {
             version: RulesVersion::default(),
             monster_hp: MonsterHitPoints::default(),
+            critical_hits: CriticalHits::default(),
+            death_saves: DeathSaveRolls::default(),
+            concentration_checks: ConcentrationChecks::default(),
         }
     }
 }
@@ -219,9 +270,15 @@ mod tests {
         let rules = RulesSettings {
             version: RulesVersion::Srd51,
             monster_hp: MonsterHitPoints::Fixed,
+            critical_hits: CriticalHits::MaxPlusRoll,
+            death_saves: DeathSaveRolls::Manual,
+            concentration_checks: ConcentrationChecks::Manual,
         };
         let toml = toml::to_string(&rules).unwrap();
         assert!(toml.contains("version = \"5.1\""));
         assert!(toml.contains("monster_hp = \"fixed\""));
+        assert!(toml.contains("critical_hits = \"max_plus_roll\""));
+        assert!(toml.contains("death_saves = \"manual\""));
+        assert!(toml.contains("concentration_checks = \"manual\""));
     }
 }
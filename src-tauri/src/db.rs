@@ -5,7 +5,7 @@
 //!
 //! This module provides:
 //! - Connection management for per-campaign SQLite databases
-//! - Query functions for monsters, players, encounters, etc.
+//! - Query functions for monsters, players, encounters, the roll log, etc.
 //! - Schema migration support
 
 mod connection;
@@ -13,9 +13,11 @@ mod error;
 mod monsters;
 mod parties;
 mod players;
+mod roll_log;
 
 pub use connection::CampaignDb;
 pub use error::DbError;
 pub use monsters::{MonsterData, MonsterRecord};
 pub use parties::{PartyData, PartyRecord};
 pub use players::{PlayerClassData, PlayerClassRecord, PlayerData, PlayerRecord};
+pub use roll_log::{RollLogData, RollLogFilter, RollLogRecord};
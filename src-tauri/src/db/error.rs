@@ -27,6 +27,10 @@ pub enum DbError {
     #[error("player not found: {0}")]
     PlayerNotFound(i64),
 
+    /// Roll not found in the roll log by ID.
+    #[error("roll not found: {0}")]
+    RollNotFound(i64),
+
     /// Encounter not found by ID.
     #[error("encounter not found: {0}")]
     EncounterNotFound(i64),
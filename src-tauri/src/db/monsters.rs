@@ -3,10 +3,13 @@
 
 //! Monster CRUD operations for the campaign database.
 
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
 use super::{CampaignDb, DbError};
+use crate::config::MonsterHitPoints;
+use crate::dice::{self, DiceExpr};
 
 /// A monster template stored in the database.
 ///
@@ -113,6 +116,41 @@ pub struct MonsterData {
     pub notes: Option<String>,
 }
 
+impl MonsterRecord {
+    /// Parses the monster's hit dice, which are stored as entered. Parsed
+    /// expressions compare equal when they are spellings of the same dice
+    /// (e.g. "3d6+3" and "3 + 3d6"), so use them to compare or deduplicate
+    /// hit dice.
+    pub fn hit_dice(&self) -> dice::Result<DiceExpr> {
+        DiceExpr::parse(&self.hit_dice)
+    }
+
+    /// Determines the hit points of a new instance of the monster from its hit
+    /// dice. With [`MonsterHitPoints::Fixed`], this is the exact average of
+    /// the hit dice rounded down (e.g. 13 for "3d6 + 3"); otherwise the hit
+    /// dice are rolled. A monster always has at least 1 hit point.
+    ///
+    /// Nothing spawns monsters from records yet; encounters are built from
+    /// [`Monster`](crate::game::Monster) values directly. Code that spawns a
+    /// monster from its record should take its hit points from here.
+    pub fn hit_points<TRng: Rng>(
+        &self,
+        rule: MonsterHitPoints,
+        rng: &mut TRng,
+    ) -> dice::Result<i32> {
+        let expr = self.hit_dice()?;
+        let hp = match rule {
+            MonsterHitPoints::Fixed => {
+                let average = expr.expected_value()?.floor();
+                average.clamp(i32::MIN as i128, i32::MAX as i128) as i32
+            }
+            MonsterHitPoints::Rolled => expr.roll(rng)?.value,
+        };
+
+        Ok(hp.max(1))
+    }
+}
+
 impl CampaignDb {
     /// Inserts a new monster into the database.
     ///
@@ -247,3 +285,69 @@ impl CampaignDb {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    fn goblin(hit_dice: &str) -> MonsterRecord {
+        MonsterRecord {
+            id: 1,
+            name: "Goblin".into(),
+            subtype: "Small Humanoid (Goblin)".into(),
+            str: 8,
+            dex: 14,
+            con: 10,
+            int: 10,
+            wis: 8,
+            cha: 8,
+            cr: 0,
+            ac: 15,
+            initiative_bonus: 2,
+            hit_dice: hit_dice.into(),
+            legendary_actions: 0,
+            portrait: None,
+            notes: None,
+        }
+    }
+
+    #[test]
+    fn test_hit_dice() {
+        let hit_dice = goblin("2d8+2").hit_dice().unwrap();
+        assert_eq!(hit_dice, goblin("2 + 2d8").hit_dice().unwrap());
+        assert_eq!(hit_dice, goblin("d8 + 1d8 + 1 + 1").hit_dice().unwrap());
+        assert_ne!(hit_dice, goblin("2d8 - 2").hit_dice().unwrap());
+        assert_eq!(hit_dice.to_string(), "2d8 + 2");
+        assert!(goblin("2d8 +").hit_dice().is_err());
+    }
+
+    #[test]
+    fn test_hit_points() {
+        let mut rng = StdRng::seed_from_u64(0x5eed);
+        let fixed = |hit_dice: &str| {
+            goblin(hit_dice)
+                .hit_points(MonsterHitPoints::Fixed, &mut StdRng::seed_from_u64(0))
+                .unwrap()
+        };
+
+        assert_eq!(fixed("2d8+2"), 11);
+        assert_eq!(fixed("3d6 + 3"), 13);
+        assert_eq!(fixed("1d4-3"), 1);
+
+        let record = goblin("2d8+2");
+        let expr = record.hit_dice().unwrap();
+        let range = expr.min().unwrap()..=expr.max().unwrap();
+        for _ in 0..100 {
+            let hp = record
+                .hit_points(MonsterHitPoints::Rolled, &mut rng)
+                .unwrap();
+            assert!(range.contains(&hp), "{hp}");
+        }
+
+        assert!(goblin("2d8 +")
+            .hit_points(MonsterHitPoints::Fixed, &mut rng)
+            .is_err());
+    }
+}
@@ -47,6 +47,15 @@ pub struct PlayerRecord {
     /// Initiative bonus.
     pub initiative_bonus: i32,
 
+    /// Current hit points.
+    pub hp: i32,
+
+    /// Current temporary hit points.
+    pub temp_hp: i32,
+
+    /// Maximum hit points.
+    pub max_hp: i32,
+
     /// Portrait base name (without path or extension).
     pub portrait: Option<String>,
 
@@ -105,6 +114,15 @@ pub struct PlayerData {
     /// Initiative bonus.
     pub initiative_bonus: i32,
 
+    /// Current hit points.
+    pub hp: i32,
+
+    /// Current temporary hit points.
+    pub temp_hp: i32,
+
+    /// Maximum hit points.
+    pub max_hp: i32,
+
     /// Portrait base name (without path or extension).
     pub portrait: Option<String>,
 
@@ -132,9 +150,9 @@ impl CampaignDb {
             r#"
             INSERT INTO Player (
                 party_id, name, str, dex, con, int, wis, cha,
-                ac, initiative_bonus, portrait, notes
+                ac, initiative_bonus, hp, temp_hp, max_hp, portrait, notes
             )
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(player.party_id)
@@ -147,6 +165,9 @@ impl CampaignDb {
         .bind(player.cha)
         .bind(player.ac)
         .bind(player.initiative_bonus)
+        .bind(player.hp)
+        .bind(player.temp_hp)
+        .bind(player.max_hp)
         .bind(&player.portrait)
         .bind(&player.notes)
         .execute(self.pool())
@@ -164,7 +185,7 @@ impl CampaignDb {
             SELECT
                 id, party_id, name,
                 str, dex, con, int, wis, cha,
-                ac, initiative_bonus, portrait, notes
+                ac, initiative_bonus, hp, temp_hp, max_hp, portrait, notes
             FROM Player
             WHERE id = ?
             "#,
@@ -184,7 +205,7 @@ impl CampaignDb {
             SELECT
                 id, party_id, name,
                 str, dex, con, int, wis, cha,
-                ac, initiative_bonus, portrait, notes
+                ac, initiative_bonus, hp, temp_hp, max_hp, portrait, notes
             FROM Player
             ORDER BY name ASC
             "#,
@@ -204,7 +225,7 @@ impl CampaignDb {
             SELECT
                 id, party_id, name,
                 str, dex, con, int, wis, cha,
-                ac, initiative_bonus, portrait, notes
+                ac, initiative_bonus, hp, temp_hp, max_hp, portrait, notes
             FROM Player
             WHERE party_id = ?
             ORDER BY name ASC
@@ -226,7 +247,8 @@ impl CampaignDb {
             UPDATE Player SET
                 party_id = ?, name = ?,
                 str = ?, dex = ?, con = ?, int = ?, wis = ?, cha = ?,
-                ac = ?, initiative_bonus = ?, portrait = ?, notes = ?
+                ac = ?, initiative_bonus = ?, hp = ?, temp_hp = ?, max_hp = ?,
+                portrait = ?, notes = ?
             WHERE id = ?
             "#,
         )
@@ -240,6 +262,9 @@ impl CampaignDb {
         .bind(player.cha)
         .bind(player.ac)
         .bind(player.initiative_bonus)
+        .bind(player.hp)
+        .bind(player.temp_hp)
+        .bind(player.max_hp)
         .bind(&player.portrait)
         .bind(&player.notes)
         .bind(id)
@@ -0,0 +1,274 @@
+// Copyright (c) 2025 Jonathon B. Cobb
+// Licensed under the MIT License
+
+//! Roll log operations for the campaign database.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Json, FromRow};
+use ts_rs::TS;
+
+use super::{CampaignDb, DbError};
+use crate::config::CriticalHits;
+use crate::dice::eval::DieRoll;
+
+/// A dice roll recorded in the roll log.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export)]
+#[serde(rename_all = "camelCase")]
+pub struct RollLogRecord {
+    /// Database ID.
+    #[ts(type = "number")]
+    pub id: i64,
+
+    /// When the roll was made (RFC 3339 format).
+    pub timestamp: String,
+
+    /// The dice expression that was rolled.
+    pub expression: String,
+
+    /// The result of the roll.
+    pub value: i32,
+
+    /// The individual dice rolled.
+    #[sqlx(json)]
+    pub dice: Vec<DieRoll>,
+
+    /// A step-by-step explanation of the roll.
+    pub explanation: String,
+
+    /// The seed of the random number generator used for the roll.
+    #[ts(type = "number")]
+    pub seed: i64,
+
+    /// The stream of the random number generator used for the roll.
+    #[ts(type = "number")]
+    pub stream: i64,
+
+    /// What the roll was for, e.g. "Attack".
+    pub label: Option<String>,
+
+    /// Who made the roll, e.g. the name of a participant.
+    pub roller: Option<String>,
+
+    /// Whether the roll was made as a critical hit.
+    pub critical: bool,
+
+    /// The campaign's rule for critical hits when the roll was made.
+    #[sqlx(json)]
+    #[ts(type = "\"double_dice\" | \"max_plus_roll\"")]
+    pub critical_rule: CriticalHits,
+
+    /// The ID of the participant the roll was made for, if any.
+    pub participant: Option<u32>,
+
+    /// The value of each variable the roll used.
+    #[sqlx(json)]
+    pub variables: BTreeMap<String, i32>,
+}
+
+/// Data for recording a roll.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RollLogData {
+    /// When the roll was made (RFC 3339 format).
+    pub timestamp: String,
+
+    /// The dice expression that was rolled.
+    pub expression: String,
+
+    /// The result of the roll.
+    pub value: i32,
+
+    /// The individual dice rolled.
+    pub dice: Vec<DieRoll>,
+
+    /// A step-by-step explanation of the roll.
+    pub explanation: String,
+
+    /// The seed of the random number generator used for the roll.
+    pub seed: i64,
+
+    /// The stream of the random number generator used for the roll.
+    pub stream: i64,
+
+    /// What the roll was for.
+    pub label: Option<String>,
+
+    /// Who made the roll.
+    pub roller: Option<String>,
+
+    /// Whether the roll was made as a critical hit.
+    pub critical: bool,
+
+    /// The campaign's rule for critical hits when the roll was made.
+    pub critical_rule: CriticalHits,
+
+    /// The ID of the participant the roll was made for.
+    pub participant: Option<u32>,
+
+    /// The value of each variable the roll used.
+    pub variables: BTreeMap<String, i32>,
+}
+
+/// Criteria for selecting rolls from the roll log. Unset criteria match any
+/// roll.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "camelCase")]
+pub struct RollLogFilter {
+    /// Only rolls made by this roller.
+    pub roller: Option<String>,
+
+    /// Only rolls with this label.
+    pub label: Option<String>,
+
+    /// Only rolls whose expression contains this text (ignoring case).
+    pub expression: Option<String>,
+}
+
+impl RollLogRecord {
+    /// Creates a record with the given ID from roll data.
+    pub fn new(id: i64, data: RollLogData) -> Self {
+        Self {
+            id,
+            timestamp: data.timestamp,
+            expression: data.expression,
+            value: data.value,
+            dice: data.dice,
+            explanation: data.explanation,
+            seed: data.seed,
+            stream: data.stream,
+            label: data.label,
+            roller: data.roller,
+            critical: data.critical,
+            critical_rule: data.critical_rule,
+            participant: data.participant,
+            variables: data.variables,
+        }
+    }
+}
+
+impl RollLogFilter {
+    /// Returns whether a record meets the criteria of this filter.
+    pub fn matches(&self, record: &RollLogRecord) -> bool {
+        self.roller
+            .as_ref()
+            .is_none_or(|roller| record.roller.as_ref() == Some(roller))
+            && self
+                .label
+                .as_ref()
+                .is_none_or(|label| record.label.as_ref() == Some(label))
+            && self.expression.as_ref().is_none_or(|expression| {
+                record
+                    .expression
+                    .to_lowercase()
+                    .contains(&expression.to_lowercase())
+            })
+    }
+}
+
+impl CampaignDb {
+    /// Records a roll in the roll log.
+    ///
+    /// Returns the ID of the newly recorded roll.
+    pub async fn insert_roll(&self, roll: &RollLogData) -> Result<i64, DbError> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO RollLog
+                (timestamp, expression, value, dice, explanation, seed, stream, label, roller,
+                 critical, critical_rule, participant, variables)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&roll.timestamp)
+        .bind(&roll.expression)
+        .bind(roll.value)
+        .bind(Json(&roll.dice))
+        .bind(&roll.explanation)
+        .bind(roll.seed)
+        .bind(roll.stream)
+        .bind(&roll.label)
+        .bind(&roll.roller)
+        .bind(roll.critical)
+        .bind(Json(roll.critical_rule))
+        .bind(roll.participant)
+        .bind(Json(&roll.variables))
+        .execute(self.pool())
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    /// Gets a roll by ID.
+    ///
+    /// Returns `DbError::RollNotFound` if the roll doesn't exist.
+    pub async fn get_roll(&self, id: i64) -> Result<RollLogRecord, DbError> {
+        sqlx::query_as::<_, RollLogRecord>(
+            r#"
+            SELECT id, timestamp, expression, value, dice, explanation, seed, stream, label, roller,
+                critical, critical_rule, participant, variables
+            FROM RollLog
+            WHERE id = ?
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(self.pool())
+        .await?
+        .ok_or(DbError::RollNotFound(id))
+    }
+
+    /// Lists a page of the rolls that match `filter`.
+    ///
+    /// Returns rolls from newest to oldest, skipping the first `offset`.
+    pub async fn list_rolls(
+        &self,
+        filter: &RollLogFilter,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<RollLogRecord>, DbError> {
+        let rolls = sqlx::query_as::<_, RollLogRecord>(
+            r#"
+            SELECT id, timestamp, expression, value, dice, explanation, seed, stream, label, roller,
+                critical, critical_rule, participant, variables
+            FROM RollLog
+            WHERE (?1 IS NULL OR roller = ?1)
+                AND (?2 IS NULL OR label = ?2)
+                AND (?3 IS NULL OR expression LIKE '%' || ?3 || '%' ESCAPE '\')
+            ORDER BY id DESC
+            LIMIT ?4 OFFSET ?5
+            "#,
+        )
+        .bind(&filter.roller)
+        .bind(&filter.label)
+        .bind(filter.expression.as_deref().map(escape_like))
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(self.pool())
+        .await?;
+
+        Ok(rolls)
+    }
+
+    /// Deletes every roll in the roll log.
+    pub async fn clear_rolls(&self) -> Result<(), DbError> {
+        sqlx::query("DELETE FROM RollLog")
+            .execute(self.pool())
+            .await?;
+        Ok(())
+    }
+}
+
+/// Escapes the wildcards of a `LIKE` pattern, so that `%` and `_` in `text`
+/// match themselves.
+fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -5,10 +5,13 @@
 //! such as `3d8 + 2`. The normal arithmetic  operations `+`, `-`, `*`, and `/`
 //! are supported with `×` and `÷` recognized as alternate forms of `*` and `/`.
 //! Products precede sums unless grouped be parentheses. Dice rolls are
-//! expressed as *count*`d`*sides* where *sides* is 4, 6, 8, 10, 12, 20, or 100
-//! (the sequence `d%` is interpreted as `d100`). If the die count is omitted
-//! (e.g. `d20 + 5`), it is assumed to be 1 and if the number of sides are
-//! omitted (e.g. `4d + 1`), then the dice are assumed to be six-sided. A roll
+//! expressed as *count*`d`*sides* where *sides* is any positive integer (the
+//! sequence `d%` is interpreted as `d100`). The number of sides may also be
+//! computed by a parenthesized expression, such as `d(2*3)`. If the die count
+//! is omitted (e.g. `d20 + 5`), it is assumed to be 1 and if the number of
+//! sides are omitted (e.g. `4d + 1`), then the dice are assumed to be
+//! six-sided. Fate/Fudge dice, with the faces -1, 0, and +1, are written as
+//! `dF` (e.g. `4dF`). A roll
 //! may be followed by any number of selection modifiers, too keep or discard
 //! certain dice:
 //!
@@ -22,6 +25,15 @@
 //!   assumed to be 1.
 //! - `adv` or `ad`: reroll the preceding expression and take the higher result.
 //! - `dis` or `da`: reroll the preceding expression and take the lower result.
+//! - `!`: roll an additional die whenever a die rolls its maximum value. The
+//!   additional dice may explode again. A target such as `!>=5` or `!1` may be
+//!   given to explode on other values instead.
+//! - `!!`: like `!`, but additional rolls are added to the die that exploded
+//!   rather than counted as separate dice.
+//! - `r<target>`: discard and reroll any die that meets the target (e.g.
+//!   `d20r1` or `4d6r<=2`) until it no longer does.
+//! - `ro<target>`: discard and reroll any die that meets the target once,
+//!   keeping the new result even if it meets the target again.
 //!
 //! For example, to roll 4d6 and keep the highest 3 (common in D&D character
 //! generation), you could write `4d6kh3` or equivalently `4d6d1` (roll 4d6 and
@@ -32,9 +44,75 @@
 //! (including any previous selections) and taking the higher or lower total
 //! respectively.
 //!
+//! A roll may also be followed by a target to count successes instead of
+//! summing the dice. Each kept die that meets the target counts as one
+//! success, so `8d6>=5` counts the dice that rolled 5 or 6. The target may be
+//! introduced with `cs` (e.g. `8d6cs5`), which is required if the target
+//! would otherwise be read as part of a preceding selection, such as
+//! `8d6!cs>=5`. A failure target introduced with `f` subtracts one from the
+//! count for each die that meets it, so `10d10cs>=8f1` counts the dice that
+//! rolled 8 or more less the dice that rolled 1. The failure target may also
+//! be written as a subtraction, e.g. `10d10cs>=8 - f1`.
+//!
+//! A term may be followed by a label in square brackets, such as a damage type
+//! in `2d6[fire] + 1d8[slashing] + 3`. The label applies to the whole term
+//! before it, so `2 * 1d6[fire]` labels the doubled roll. Labels do not change
+//! the result, but the result of a roll includes the subtotal of each label. A
+//! subtracted label counts against its subtotal, so `bane` in
+//! `1d20 - 1d4[bane]` is negative.
+//!
+//! Variables such as `@dex` or `@pb` may appear anywhere an integer may, e.g.
+//! `d20 + @dex + @pb`. They are resolved when the expression is rolled by an
+//! [`eval::Resolver`], such as the ability modifiers and proficiency bonus of
+//! a participant. Rolling an expression with unbound variables is an error.
+//!
+//! Two expressions may be compared with `=`, `<`, `<=`, `>`, or `>=`, giving 1
+//! if the comparison holds and 0 otherwise. Since a comparison directly after
+//! a roll counts successes, a roll must be parenthesized to compare its total,
+//! as in `(d20) >= 15`, whereas `d20 + 6 >= 15` compares the whole sum. A
+//! conditional `a ? b : c` evaluates only `b` if `a` is nonzero and only `c`
+//! otherwise, so `(d20 + 6 >= 15) ? 2d6 + 4 : 0` rolls damage only on a hit.
+//! Attacks can also be written as `hit(d20 + 6, 15, 2d6 + 4)`, which rolls the
+//! damage if the attack meets the target, except that a natural 20 on the
+//! attack's d20 always hits and rolls the damage as a critical hit, and a
+//! natural 1 always misses. The result of a roll records which branch each
+//! conditional and attack took.
+//!
+//! Several independent expressions may be rolled at once as a batch, either by
+//! repeating one expression, as in `6x(4d6kh3)`, or by listing them in braces,
+//! as in `{d20 + 5, d20 + 5, d20 + 3}`. A batch produces one result for each
+//! expression and may only appear at the top level.
+//!
 //! Only integers are supported, and the result of an expression is always an
-//! integer. When division is performed, the result is rounded down to the
-//! nearest integer before the next operation is performed.
+//! integer. When division is performed, the result is truncated toward zero
+//! before the next operation is performed.
+//!
+//! The built-in functions `min`, `max`, `floor`, `ceil`, `round`, and `abs`
+//! may be called anywhere an integer may appear, as in `max(1, d4 - 1)`. When
+//! the argument of `floor`, `ceil`, or `round` is a division, the quotient is
+//! rounded down, rounded up, or rounded to the nearest integer (with halves
+//! rounded up) instead of being truncated, so `ceil(@level / 2)` rounds up.
+//!
+//! Notes may embed inline rolls such as `[[d20 + 5|Bite]]`, which
+//! [`markup::Notes`] extracts and checks so that they can be rolled on demand.
+//!
+//! Syntax errors report the byte offsets of the offending input, the tokens
+//! that would have been valid there, and, for a misspelled word or mismatched
+//! bracket, a suggested correction (e.g. `kh` for `khh`).
+//!
+//! [`DiceExpr::normalize`] rewrites an expression into a canonical form, folding
+//! constants, merging like dice and ordering terms, so that two spellings of
+//! the same expression compare equal and display the same.
+//!
+//! [`DiceExpr::expected_value`] computes the exact average of an expression as
+//! an [`expect::Ratio`], taking selections such as `4d6kh3` and `d20adv` into
+//! account, whereas [`DiceExpr::mid`] takes the middle face of each die.
+//!
+//! Evaluation is limited by an [`eval::Budget`] on the total number of dice
+//! rolled and the depth of nesting, so a roll such as `999999999d100` fails
+//! instead of running indefinitely. Arithmetic that overflows also fails rather
+//! than wrapping. Expressions nested more deeply than [`parser::MAX_DEPTH`]
+//! are rejected by the parser, before any other pass over them.
 //!
 //! Grammar
 //! -------
@@ -42,12 +120,21 @@
 //! Dice expressions are parsed according to the following grammar:
 //!
 //! ```ebnf
-//! root = sum;
-//! sum = term, { ("+" | "-"), term };
-//! term = factor, { ("*" | "/"), factor };
-//! factor = "(", sum, ")" | negation | integer | roll;
+//! batch = repeat | list | root;
+//! repeat = integer, "x", "(", conditional, ")";
+//! list = "{", conditional, { ",", conditional }, "}";
+//! root = conditional;
+//! conditional = condition, ["?", conditional, ":", conditional];
+//! condition = sum, [comparison, sum];
+//! sum = term, { ("+" | "-"), term | "-", "f", target };
+//! term = factor, [label], { ("*" | "/"), factor, [label] };
+//! factor = "(", conditional, ")" | call | hit | negation | integer | variable | roll, [count];
+//! call = function, "(", conditional, { ",", conditional }, ")";
+//! function = "min" | "max" | "floor" | "ceil" | "round" | "abs";
+//! hit = "hit", "(", conditional, ",", conditional, ",", conditional, ")";
 //! negation = "-", factor;
-//! roll = [integer], "d", [integer], [selection];
+//! roll = [integer], ("d", [sides] | "dF"), [selection];
+//! sides = integer | "%" | "(", sum, ")";
 //! selection = (
 //!         "k", integer |
 //!         "kh", integer |
@@ -56,28 +143,44 @@
 //!         "dh", integer |
 //!         "dl", integer |
 //!         "adv" | "ad" |
-//!         "dis" | "da"
+//!         "dis" | "da" |
+//!         "!", [target] |
+//!         "!!", [target] |
+//!         "r", target |
+//!         "ro", target
 //!     ), [selection];
+//! count = ("cs", target | comparison, integer), ["f", target];
+//! target = [comparison], integer;
+//! comparison = "=" | "<" | "<=" | ">" | ">=";
+//! label = "[", /[A-Za-z][A-Za-z _-]*/, "]";
+//! variable = "@", /[A-Za-z0-9_]+/;
 //! integer = /[0-9]+/;
 //! ```
 
 use std::{
+    collections::BTreeMap,
     fmt::Debug,
     fmt::{Display, Formatter, Result as FmtResult},
+    hash::{Hash, Hasher},
     result::Result as StdResult,
 };
 
-use eval::DieRoll;
-use rand::{rngs::StdRng, Rng};
+use ast::Comparison;
+use eval::{Branch, Critical, DieRoll, Resolver};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use ts_rs::TS;
 
 pub mod ast;
+pub mod dist;
 pub mod eval;
+pub mod expect;
 pub mod lexer;
 pub mod lookahead;
+pub mod markup;
 pub mod parser;
 pub mod pp;
+pub mod simplify;
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -85,6 +188,8 @@ pub enum Error {
     ParseError(#[from] parser::Error),
     #[error("Evaluation error {0}")]
     EvalError(#[from] eval::Error),
+    #[error("Distribution error {0}")]
+    DistError(#[from] dist::Error),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -94,6 +199,110 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub struct Roll {
     pub value: i32,
     pub dice: Vec<DieRoll>,
+
+    /// The subtotal of each label in the expression, such as `fire` in
+    /// `2d6[fire] + 3`.
+    #[serde(default)]
+    pub breakdown: BTreeMap<String, i32>,
+
+    /// The branches taken by conditionals and attacks in the expression, in
+    /// the order they were evaluated.
+    #[serde(default)]
+    pub branches: Vec<Branch>,
+
+    /// The value of each variable in the expression, such as `dex` in
+    /// `d20 + @dex`, so that the roll can be replayed after it changes.
+    #[serde(default)]
+    pub variables: BTreeMap<String, i32>,
+
+    /// A step-by-step explanation of the roll, such as
+    /// `4d6kh3 → [6, 5, 3, ~1~] = 14; 14 + 2 = 16`.
+    #[serde(default)]
+    pub explanation: String,
+
+    /// The steps of the explanation: each roll with its dice and total,
+    /// followed by the arithmetic that combines them.
+    #[serde(default)]
+    pub trace: Vec<pp::Step>,
+
+    /// The seed of the random number generator used for the roll.
+    #[serde(default)]
+    #[ts(type = "number")]
+    pub seed: u64,
+
+    /// The stream of the random number generator used for the roll. Rolling
+    /// the same expression with the same seed and stream replays the roll.
+    #[serde(default)]
+    #[ts(type = "number")]
+    pub stream: u64,
+}
+
+/// The probability distribution of the results of a dice expression.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Distribution {
+    /// Each possible result and its probability, from lowest to highest.
+    pub outcomes: Vec<Probability>,
+
+    /// Whether the probabilities are exact or were estimated by sampling
+    /// because the expression is too complex to analyze exactly.
+    pub exact: bool,
+}
+
+/// The probability of a single result of a dice expression.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Probability {
+    /// The result.
+    pub value: i32,
+
+    /// The probability of the result, between 0 and 1.
+    pub probability: f64,
+}
+
+impl Distribution {
+    /// Returns the probability that a result compares to `target` as given by
+    /// `comparison`, e.g. the chance to roll at least 15.
+    pub fn probability(&self, comparison: Comparison, target: i32) -> f64 {
+        self.outcomes
+            .iter()
+            .filter(|outcome| eval::compare(comparison, outcome.value, target))
+            .map(|outcome| outcome.probability)
+            .sum()
+    }
+
+    /// Returns the expected (mean) result.
+    pub fn mean(&self) -> f64 {
+        self.outcomes
+            .iter()
+            .map(|outcome| outcome.value as f64 * outcome.probability)
+            .sum()
+    }
+}
+
+/// The number of rolls used to estimate a distribution by sampling.
+const SAMPLES: usize = 100_000;
+
+/// The greatest number of dice rolled across all the rolls used to estimate a
+/// distribution, so that expressions rolling many dice are sampled fewer times.
+const SAMPLE_DICE: usize = 10_000_000;
+
+/// The number of bits in a seed, small enough for seeds to survive the round
+/// trip through a JavaScript number.
+const SEED_BITS: u32 = 53;
+
+/// Generates a new seed for [`DiceExpr::roll_seeded`].
+pub fn random_seed<TRng: Rng>(rng: &mut TRng) -> u64 {
+    rng.gen::<u64>() >> (u64::BITS - SEED_BITS)
+}
+
+/// Creates the random number generator identified by `seed` and `stream`.
+/// Distinct streams with the same seed produce independent sequences.
+fn seeded_rng(seed: u64, stream: u64) -> StdRng {
+    let mut bytes = <StdRng as SeedableRng>::Seed::default();
+    bytes[..8].copy_from_slice(&seed.to_le_bytes());
+    bytes[8..16].copy_from_slice(&stream.to_le_bytes());
+    StdRng::from_seed(bytes)
 }
 
 #[derive(Clone)]
@@ -107,15 +316,164 @@ impl DiceExpr {
         Ok(DiceExpr { root })
     }
 
+    /// Returns an equivalent expression in canonical form, with constants
+    /// folded, like dice merged and terms in a canonical order. Its
+    /// [`Display`] form is stable across spellings of the same expression,
+    /// e.g. both `d6 + 2d6 + 1` and `1 + 3d6` normalize to `3d6 + 1`.
+    pub fn normalize(&self) -> DiceExpr {
+        DiceExpr {
+            root: simplify::normalize(self.root.as_ref()),
+        }
+    }
+
+    /// Rolls the expression with a new seed drawn from `rng`.
     pub fn roll<TRng: Rng>(&self, rng: &mut TRng) -> Result<Roll> {
-        let mut evaluator = eval::Evaluator::new(eval::Evaluation::Rand(rng));
+        self.roll_seeded(random_seed(rng), 0, Critical::default(), false, None)
+    }
+
+    /// Rolls the expression, resolving any variables with `resolver`.
+    pub fn roll_with<TRng: Rng>(&self, rng: &mut TRng, resolver: &dyn Resolver) -> Result<Roll> {
+        self.roll_seeded(
+            random_seed(rng),
+            0,
+            Critical::default(),
+            false,
+            Some(resolver),
+        )
+    }
+
+    /// Rolls the expression as a critical hit, adding extra dice to every roll
+    /// according to `critical`. Flat modifiers are not affected, so `1d8 + 3`
+    /// rolls as `2d8 + 3` when doubling dice.
+    pub fn roll_critical<TRng: Rng>(&self, rng: &mut TRng, critical: Critical) -> Result<Roll> {
+        self.roll_seeded(random_seed(rng), 0, critical, true, None)
+    }
+
+    /// Rolls the expression with the random number generator identified by
+    /// `seed` and `stream`, resolving variables with `resolver`. Critical hits,
+    /// whether `forced` for the whole expression or rolled as a natural 20 by
+    /// `hit`, add extra dice according to `critical`. Rolling again with the
+    /// same arguments replays the roll exactly.
+    pub fn roll_seeded(
+        &self,
+        seed: u64,
+        stream: u64,
+        critical: Critical,
+        forced: bool,
+        resolver: Option<&dyn Resolver>,
+    ) -> Result<Roll> {
+        let mut rng = seeded_rng(seed, stream);
+        let evaluation = if forced {
+            eval::Evaluation::Critical(&mut rng)
+        } else {
+            eval::Evaluation::Rand(&mut rng)
+        };
+        let mut evaluator = match resolver {
+            Some(resolver) => eval::Evaluator::with_resolver(evaluation, resolver),
+            None => eval::Evaluator::new(evaluation),
+        }
+        .with_critical_rule(critical);
+
         let value = evaluator.eval(self.root.as_ref())?;
+        let annotation = pp::Annotation::new(
+            &evaluator.rolls,
+            &evaluator.pools,
+            &evaluator.branches,
+            resolver,
+        );
+        let trace = pp::explain(self.root.as_ref(), value, annotation).unwrap_or_default();
+        let explanation = trace
+            .iter()
+            .map(|step| step.to_string())
+            .collect::<Vec<_>>()
+            .join("; ");
+
         Ok(Roll {
             value,
             dice: evaluator.rolls.clone(),
+            breakdown: evaluator.breakdown.clone(),
+            branches: evaluator.branches.clone(),
+            variables: evaluator.variables.clone(),
+            explanation,
+            trace,
+            seed,
+            stream,
         })
     }
 
+    /// Computes the probability of each possible result of the expression.
+    ///
+    /// The distribution is exact unless the expression is too complex to
+    /// analyze, in which case it is estimated by sampling. Sampling may take a
+    /// while, so callers on an async runtime should run it as blocking work.
+    pub fn distribution(&self) -> Result<Distribution> {
+        let (pmf, exact) = match dist::distribution(self.root.as_ref()) {
+            Ok(pmf) => (pmf, true),
+            Err(dist::Error::Intractable) => (self.sample(&mut rand::thread_rng())?, false),
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(Distribution {
+            outcomes: pmf
+                .into_iter()
+                .map(|(value, probability)| Probability { value, probability })
+                .collect(),
+            exact,
+        })
+    }
+
+    /// Computes the probability that the result of the expression compares to
+    /// `target` as given by `comparison`. For example, the chance of hitting
+    /// AC 15 with `d20adv+7` is
+    /// `expr.probability(Comparison::GreaterOrEqual, 15)`.
+    pub fn probability(&self, comparison: Comparison, target: i32) -> Result<f64> {
+        Ok(self.distribution()?.probability(comparison, target))
+    }
+
+    fn sample<TRng: Rng>(&self, rng: &mut TRng) -> Result<dist::Pmf> {
+        let mut evaluator = eval::Evaluator::new(eval::Evaluation::Rand(rng));
+        let mut counts = BTreeMap::new();
+        let mut samples = 0;
+        let mut dice = 0;
+
+        while samples < SAMPLES && dice < SAMPLE_DICE {
+            let value = evaluator.eval(self.root.as_ref())?;
+            *counts.entry(value).or_insert(0) += 1;
+            samples += 1;
+            dice += evaluator.dice_rolled();
+        }
+
+        Ok(counts
+            .into_iter()
+            .map(|(value, count)| (value, count as f64 / samples as f64))
+            .collect())
+    }
+
+    /// Computes the exact expected (average) result of the expression, e.g.
+    /// 7 for `2d6` or 553/40 for `d20adv`.
+    ///
+    /// Expressions that divide, compare, explode or reroll dice are too complex
+    /// to analyze exactly and give [`dist::Error::Intractable`]; see
+    /// [`DiceExpr::mean`].
+    pub fn expected_value(&self) -> Result<expect::Ratio> {
+        Ok(expect::expected_value(&simplify::normalize(
+            self.root.as_ref(),
+        ))?)
+    }
+
+    /// Returns the expected (mean) result of the expression, computed exactly
+    /// where possible and otherwise from its [distribution](Self::distribution).
+    pub fn mean(&self) -> Result<f64> {
+        match self.expected_value() {
+            Ok(value) => Ok(value.to_f64()),
+            Err(Error::DistError(dist::Error::Intractable)) => Ok(self.distribution()?.mean()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Evaluates the expression with each die showing its middle face (rounded
+    /// down), so the result may differ from the [expected
+    /// value](Self::expected_value).
     pub fn mid(&self) -> Result<i32> {
         let mut evaluator = eval::Evaluator::new(eval::Evaluation::<StdRng>::Mid);
         Ok(evaluator.eval(self.root.as_ref())?)
@@ -132,10 +490,80 @@ impl DiceExpr {
     }
 }
 
+/// A batch of independent dice expressions that are rolled together, such as
+/// `6x(4d6kh3)` or `{d20 + 5, d20 + 3}`.
+#[derive(Clone, Debug)]
+pub struct DiceBatch {
+    exprs: Vec<DiceExpr>,
+}
+
+impl DiceBatch {
+    /// Parses a batch. A single expression is parsed as a batch of one.
+    pub fn parse(input: &str) -> Result<Self> {
+        let exprs = parser::parse_batch(input)?
+            .into_iter()
+            .map(|root| DiceExpr { root })
+            .collect();
+        Ok(DiceBatch { exprs })
+    }
+
+    /// The expressions in the batch, in order.
+    pub fn exprs(&self) -> &[DiceExpr] {
+        &self.exprs
+    }
+
+    /// Rolls every expression in the batch with a new seed drawn from `rng`.
+    pub fn roll<TRng: Rng>(&self, rng: &mut TRng) -> Result<Vec<Roll>> {
+        self.roll_seeded(random_seed(rng), 0, Critical::default(), false, None)
+    }
+
+    /// Rolls every expression in the batch as with
+    /// [`DiceExpr::roll_seeded`]. Each expression is rolled with its own
+    /// stream, counting up from `stream`, so the batch uses as many streams as
+    /// it has expressions.
+    pub fn roll_seeded(
+        &self,
+        seed: u64,
+        stream: u64,
+        critical: Critical,
+        forced: bool,
+        resolver: Option<&dyn Resolver>,
+    ) -> Result<Vec<Roll>> {
+        self.exprs
+            .iter()
+            .zip(stream..)
+            .map(|(expr, stream)| expr.roll_seeded(seed, stream, critical, forced, resolver))
+            .collect()
+    }
+}
+
 impl Display for DiceExpr {
+    /// Formats the expression in canonical form or, with the alternate flag
+    /// (`{:#}`), as written.
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        if f.alternate() {
+            return pp::PP::new(f).write(self.root.as_ref());
+        }
+
+        let root = simplify::normalize(self.root.as_ref());
         let mut pp = pp::PP::new(f);
-        pp.write(self.root.as_ref())
+        pp.write(root.as_ref())
+    }
+}
+
+/// Expressions are equal if they have the same canonical form, so that
+/// different spellings of the same expression compare and hash as equal.
+impl PartialEq for DiceExpr {
+    fn eq(&self, other: &Self) -> bool {
+        simplify::normalize(self.root.as_ref()) == simplify::normalize(other.root.as_ref())
+    }
+}
+
+impl Eq for DiceExpr {}
+
+impl Hash for DiceExpr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        simplify::normalize(self.root.as_ref()).hash(state);
     }
 }
 
@@ -164,3 +592,791 @@ pub fn roll<TRng: Rng>(input: &str, rng: &mut TRng) -> Result<i32> {
     let mut evaluator = eval::Evaluator::new(eval::Evaluation::Rand(rng));
     Ok(evaluator.eval(root.as_ref())?)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::dist::tests::{assert_close, probabilities, probability};
+    use super::eval::Outcome;
+    use super::*;
+
+    fn rng() -> StdRng {
+        StdRng::seed_from_u64(0x5eed)
+    }
+
+    fn print(input: &str) -> String {
+        format!("{:#}", DiceExpr::parse(input).unwrap())
+    }
+
+    #[test]
+    fn test_separate_rolls_are_summed_independently() {
+        let expr = DiceExpr::parse("1d6 + 1d6").unwrap();
+        assert_eq!(expr.max().unwrap(), 12);
+        assert_eq!(expr.min().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_die_sizes() {
+        assert_eq!(print("d3 + 2d30"), "1d3 + 2d30");
+        assert_eq!(print("d%"), "1d100");
+        assert_eq!(print("2d(2*3)"), "2d(2 × 3)");
+        assert_eq!(print("d(1 + 1)kh1"), "1d(1 + 1)kh1");
+        assert!(DiceExpr::parse("d0").is_err());
+
+        let expr = DiceExpr::parse("3d3").unwrap();
+        assert_eq!(expr.min().unwrap(), 3);
+        assert_eq!(expr.mid().unwrap(), 6);
+        assert_eq!(expr.max().unwrap(), 9);
+
+        let expr = DiceExpr::parse("2d(2*3)").unwrap();
+        assert_eq!(expr.max().unwrap(), 12);
+
+        let expr = DiceExpr::parse("d(1 - 1)").unwrap();
+        assert!(expr.roll(&mut rng()).is_err());
+        assert!(expr.distribution().is_err());
+    }
+
+    #[test]
+    fn test_fudge_dice() {
+        assert_eq!(print("4dF"), "4dF");
+        assert_eq!(print("dF + 1"), "1dF + 1");
+        assert_eq!(print("4dFkh2"), "4dFkh2");
+
+        let expr = DiceExpr::parse("4dF").unwrap();
+        assert_eq!(expr.min().unwrap(), -4);
+        assert_eq!(expr.mid().unwrap(), 0);
+        assert_eq!(expr.max().unwrap(), 4);
+
+        let mut rng = rng();
+        for _ in 0..20 {
+            let roll = expr.roll(&mut rng).unwrap();
+            assert_eq!(roll.dice.len(), 4);
+            assert!(roll
+                .dice
+                .iter()
+                .all(|d| d.fudge && (-1..=1).contains(&d.result)));
+            assert_eq!(roll.value, roll.dice.iter().map(|d| d.result).sum::<i32>());
+        }
+
+        assert_close(probability("4dF", 4), 1.0 / 81.0);
+        assert_close(probability("4dF", 0), 19.0 / 81.0);
+        assert_close(probability("dF!", 2), 4.0 / 81.0);
+    }
+
+    #[test]
+    fn test_variables() {
+        assert_eq!(print("d20 + @dex + @pb"), "1d20 + @dex + @pb");
+        assert_eq!(print("2 * @str_mod"), "2 × @str_mod");
+        assert!(DiceExpr::parse("d20 + @").is_err());
+
+        let bindings = HashMap::from([("dex".to_string(), 3), ("pb".to_string(), 2)]);
+        let expr = DiceExpr::parse("d20 + @dex + @pb").unwrap();
+        let roll = expr.roll_with(&mut rng(), &bindings).unwrap();
+        assert_eq!(roll.value, roll.dice[0].result + 5);
+
+        // The values are kept so that the roll can be replayed with them.
+        let replayed = expr
+            .roll_seeded(
+                roll.seed,
+                roll.stream,
+                Critical::default(),
+                false,
+                Some(
+                    &roll
+                        .variables
+                        .clone()
+                        .into_iter()
+                        .collect::<HashMap<_, _>>(),
+                ),
+            )
+            .unwrap();
+        assert_eq!(
+            roll.variables,
+            BTreeMap::from([("dex".to_string(), 3), ("pb".to_string(), 2)])
+        );
+        assert_eq!(replayed.value, roll.value);
+
+        assert!(expr.roll(&mut rng()).is_err());
+        assert!(expr.max().is_err());
+    }
+
+    #[test]
+    fn test_labels() {
+        assert_eq!(
+            print("2d6[fire] + 1d8[slashing] + 3"),
+            "2d6[fire] + 1d8[slashing] + 3"
+        );
+        assert_eq!(print("2 * d6 [ cold ]"), "2 × 1d6[cold]");
+        assert_eq!(print("d6[fire] * 2"), "(1d6[fire]) × 2");
+        assert_eq!(print("(d6 + 1)[acid]"), "(1d6 + 1)[acid]");
+        assert_eq!(print("[2d6 + 1]"), "2d6 + 1");
+
+        let expr = DiceExpr::parse("2d6[fire] + 1d8[slashing] + 2d6[fire] + 3").unwrap();
+        let roll = expr.roll(&mut rng()).unwrap();
+        let fire: i32 = roll
+            .dice
+            .iter()
+            .filter(|d| d.sides == 6)
+            .map(|d| d.result)
+            .sum();
+        let slashing: i32 = roll
+            .dice
+            .iter()
+            .filter(|d| d.sides == 8)
+            .map(|d| d.result)
+            .sum();
+
+        assert_eq!(roll.breakdown.len(), 2);
+        assert_eq!(roll.breakdown["fire"], fire);
+        assert_eq!(roll.breakdown["slashing"], slashing);
+        assert_eq!(roll.value, fire + slashing + 3);
+
+        let roll = DiceExpr::parse("2 * 1d6[cold]")
+            .unwrap()
+            .roll(&mut rng())
+            .unwrap();
+        assert_eq!(roll.breakdown["cold"], roll.value);
+
+        // A subtracted label counts against its subtotal.
+        let roll = DiceExpr::parse("1d20 - 1d4[bane]")
+            .unwrap()
+            .roll(&mut rng())
+            .unwrap();
+        assert_eq!(roll.breakdown["bane"], -roll.dice[1].result);
+        assert_eq!(roll.value, roll.dice[0].result + roll.breakdown["bane"]);
+    }
+
+    #[test]
+    fn test_roll_critical() {
+        let expr = DiceExpr::parse("2d6[fire] + 3").unwrap();
+        let mut rng = rng();
+
+        for _ in 0..20 {
+            let roll = expr.roll_critical(&mut rng, Critical::DoubleDice).unwrap();
+            let total: i32 = roll.dice.iter().map(|d| d.result).sum();
+            assert_eq!(roll.dice.len(), 4);
+            assert_eq!(roll.value, total + 3);
+            assert_eq!(roll.breakdown["fire"], total);
+
+            let roll = expr.roll_critical(&mut rng, Critical::MaxPlusRoll).unwrap();
+            let total: i32 = roll.dice.iter().map(|d| d.result).sum();
+            assert_eq!(roll.dice.len(), 4);
+            assert!(roll.dice.iter().filter(|d| d.result == 6).count() >= 2);
+            assert_eq!(roll.value, total + 3);
+        }
+
+        let roll = DiceExpr::parse("d20kh1 + 5")
+            .unwrap()
+            .roll_critical(&mut rng, Critical::DoubleDice)
+            .unwrap();
+        assert_eq!(roll.dice.iter().filter(|d| d.keep).count(), 1);
+    }
+
+    #[test]
+    fn test_comparisons() {
+        assert_eq!(print("d20+6 >= 15"), "1d20 + 6 >= 15");
+        assert_eq!(print("(d20) >= 15"), "(1d20) >= 15");
+        assert_eq!(print("(2 + d20) < 5"), "(2 + 1d20) < 5");
+        assert_eq!(print("d20 >= 15"), "1d20cs>=15");
+        assert_eq!(print("1 + (3 = 3)"), "1 + (3 = 3)");
+        assert_eq!(print("2 * (1 + d6 > 4)"), "2 × (1 + 1d6cs>4)");
+        assert_eq!(print("2 * ((1 + d6) > 4)"), "2 × ((1 + 1d6) > 4)");
+        assert!(DiceExpr::parse("1 < 2 < 3").is_err());
+
+        let expr = DiceExpr::parse("(d20) >= 11").unwrap();
+        assert_eq!(expr.max().unwrap(), 1);
+        assert_eq!(expr.min().unwrap(), 0);
+        assert_close(expr.probability(Comparison::Equal, 1).unwrap(), 0.5);
+    }
+
+    #[test]
+    fn test_conditionals() {
+        assert_eq!(
+            print("(d20+6 >= 15) ? 2d6+4 : 0"),
+            "1d20 + 6 >= 15 ? 2d6 + 4 : 0"
+        );
+        assert_eq!(print("1 ? 2 : 0 ? 3 : 4"), "1 ? 2 : 0 ? 3 : 4");
+        assert_eq!(print("(1 ? 0 : 1) ? 3 : 4"), "(1 ? 0 : 1) ? 3 : 4");
+        assert_eq!(print("2 * (1 ? 2 : 3)"), "2 × (1 ? 2 : 3)");
+        assert!(DiceExpr::parse("1 ? 2").is_err());
+
+        let expr = DiceExpr::parse("(d20 + 6 >= 15) ? 2d6 + 4 : 0").unwrap();
+        assert_eq!(expr.max().unwrap(), 16);
+        assert_eq!(expr.min().unwrap(), 0);
+
+        let roll = expr.roll(&mut rng()).unwrap();
+        match roll.branches[..] {
+            [Branch::Then] => assert_eq!(roll.dice.len(), 3),
+            [Branch::Else] => assert_eq!((roll.value, roll.dice.len()), (0, 1)),
+            _ => panic!("unexpected branches {:?}", roll.branches),
+        }
+
+        // Hits on 9 or more, then averages 11 damage.
+        let dist = expr.distribution().unwrap();
+        assert!(dist.exact);
+        assert_close(dist.probability(Comparison::Equal, 0), 0.4);
+        assert_close(dist.mean(), 0.6 * 11.0);
+    }
+
+    #[test]
+    fn test_hit() {
+        assert_eq!(print("hit(d20+6, 15, 2d6+4)"), "hit(1d20 + 6, 15, 2d6 + 4)");
+        assert!(DiceExpr::parse("hit(d20, 15)").is_err());
+
+        let expr = DiceExpr::parse("hit(d20 + 6, 15, 2d6 + 4)").unwrap();
+        assert_eq!(expr.max().unwrap(), 28);
+        assert_eq!(expr.min().unwrap(), 0);
+
+        let mut rng = rng();
+        for _ in 0..200 {
+            let roll = expr.roll(&mut rng).unwrap();
+            let natural = roll.dice[0].result;
+            match roll.branches[..] {
+                [Branch::Critical] => {
+                    assert_eq!(natural, 20);
+                    assert_eq!(roll.dice.len(), 5);
+                }
+                [Branch::Hit] => {
+                    assert!((9..20).contains(&natural));
+                    assert_eq!(roll.dice.len(), 3);
+                }
+                [Branch::Miss] => {
+                    assert!(natural < 9);
+                    assert_eq!((roll.value, roll.dice.len()), (0, 1));
+                }
+                _ => panic!("unexpected branches {:?}", roll.branches),
+            }
+        }
+
+        // A natural 1 misses even when the attack would otherwise hit.
+        let expr = DiceExpr::parse("hit(d20 + 30, 15, 5)").unwrap();
+        assert_eq!(expr.min().unwrap(), 0);
+
+        // Forcing a critical hit adds dice to the damage but not the attack.
+        let roll = expr
+            .roll_seeded(0, 0, Critical::MaxPlusRoll, true, None)
+            .unwrap();
+        assert_eq!(roll.dice.len(), 1);
+
+        let expr = DiceExpr::parse("hit(d20, 20, 1d6)").unwrap();
+        let roll = expr
+            .roll_seeded(0, 0, Critical::MaxPlusRoll, true, None)
+            .unwrap();
+        assert_eq!(
+            roll.dice.len(),
+            1 + 2 * (roll.branches == [Branch::Critical]) as usize
+        );
+        assert!(!expr.distribution().unwrap().exact);
+    }
+
+    #[test]
+    fn test_functions() {
+        assert_eq!(print("max(1,d4-1)"), "max(1, 1d4 - 1)");
+        assert_eq!(print("ceil(7/2) + abs(-3)"), "ceil(7 / 2) + abs(-3)");
+        assert_eq!(
+            print("min(d20, d20, 2 ? 3 : 4)"),
+            "min(1d20, 1d20, 2 ? 3 : 4)"
+        );
+        assert!(DiceExpr::parse("abs(1, 2)").is_err());
+        assert!(DiceExpr::parse("max()").is_err());
+        assert!(DiceExpr::parse("max 2").is_err());
+
+        let value = |input: &str| DiceExpr::parse(input).unwrap().mid().unwrap();
+        assert_eq!(value("7 / 2"), 3);
+        assert_eq!(value("floor(7 / 2)"), 3);
+        assert_eq!(value("ceil(7 / 2)"), 4);
+        assert_eq!(value("round(7 / 2)"), 4);
+        assert_eq!(value("round(10 / 4)"), 3);
+        assert_eq!(value("round(9 / 4)"), 2);
+        assert_eq!(value("-7 / 2"), -3);
+        assert_eq!(value("floor(-7 / 2)"), -4);
+        assert_eq!(value("ceil(-7 / 2)"), -3);
+        assert_eq!(value("round(-7 / 2)"), -3);
+        assert_eq!(value("floor(7 / -2)"), -4);
+        assert_eq!(value("ceil(3)"), 3);
+        assert_eq!(value("abs(2 - 5)"), 3);
+        assert_eq!(value("min(4, 2, 3)"), 2);
+        assert_eq!(value("max(4, 2, 3)"), 4);
+        assert!(DiceExpr::parse("ceil(1 / 0)").unwrap().mid().is_err());
+
+        assert_eq!(probability("max(1, d4 - 1)", 1), 0.5);
+        assert_eq!(probability("max(1, d4 - 1)", 3), 0.25);
+        for value in 1..=3 {
+            assert_close(probability("ceil(d6 / 2)", value), 1.0 / 3.0);
+        }
+        assert_eq!(
+            probabilities("abs(d3 - 2)"),
+            [(0, 1.0 / 3.0), (1, 2.0 / 3.0)]
+        );
+    }
+
+    #[test]
+    fn test_batch() {
+        let batch = DiceBatch::parse("6x(4d6kh3)").unwrap();
+        assert_eq!(batch.exprs().len(), 6);
+        assert!(batch
+            .exprs()
+            .iter()
+            .all(|expr| expr.to_string() == "4d6kh3"));
+
+        let rolls = batch.roll(&mut rng()).unwrap();
+        assert_eq!(rolls.len(), 6);
+        assert!(rolls.iter().any(|roll| roll.dice != rolls[0].dice));
+        assert!(rolls.iter().all(|roll| (3..=18).contains(&roll.value)));
+
+        let batch = DiceBatch::parse("{d20 + 5, d20+5, 2d6[fire]}").unwrap();
+        let exprs: Vec<_> = batch.exprs().iter().map(|e| e.to_string()).collect();
+        assert_eq!(exprs, ["1d20 + 5", "1d20 + 5", "2d6[fire]"]);
+
+        let batch = DiceBatch::parse("2d6 + 3").unwrap();
+        assert_eq!(batch.exprs().len(), 1);
+        let batch = DiceBatch::parse("6").unwrap();
+        assert_eq!(batch.exprs()[0].max().unwrap(), 6);
+
+        assert!(DiceBatch::parse("0x(d20)").is_err());
+        assert!(DiceBatch::parse("101x(d20)").is_err());
+        assert!(DiceBatch::parse("6x4d6").is_err());
+        assert!(DiceBatch::parse("{d20, d20").is_err());
+        assert!(DiceBatch::parse("{d20, d20)").is_err());
+        assert!(DiceBatch::parse("{}").is_err());
+        assert!(DiceBatch::parse("1 + {d20}").is_err());
+        assert!(DiceExpr::parse("6x(d20)").is_err());
+        assert!(DiceExpr::parse("{d20}").is_err());
+    }
+
+    #[test]
+    fn test_roll_seeded() {
+        let expr = DiceExpr::parse("8d6!").unwrap();
+        let roll = expr.roll(&mut rng()).unwrap();
+        assert_eq!(roll.seed >> SEED_BITS, 0);
+
+        let replay = expr
+            .roll_seeded(roll.seed, roll.stream, Critical::default(), false, None)
+            .unwrap();
+        assert_eq!(replay.value, roll.value);
+        assert_eq!(replay.dice, roll.dice);
+
+        let rolls: Vec<_> = (0..10)
+            .map(|stream| {
+                expr.roll_seeded(1234, stream, Critical::default(), false, None)
+                    .unwrap()
+                    .dice
+            })
+            .collect();
+        assert!(rolls.iter().any(|dice| *dice != rolls[0]));
+
+        let critical = |seed| {
+            expr.roll_seeded(seed, 0, Critical::DoubleDice, true, None)
+                .unwrap()
+                .dice
+        };
+        assert_eq!(critical(roll.seed), critical(roll.seed));
+    }
+
+    #[test]
+    fn test_explanation() {
+        let roll = DiceExpr::parse("4d6kh3 + 2")
+            .unwrap()
+            .roll(&mut rng())
+            .unwrap();
+        assert_eq!(roll.trace.len(), 2);
+        assert_eq!(roll.trace[0].expression, "4d6kh3");
+        assert_eq!(roll.trace[0].dice.len(), 4);
+        assert_eq!(roll.trace[0].value, roll.value - 2);
+        assert_eq!(roll.trace[1].expression, format!("{} + 2", roll.value - 2));
+        assert_eq!(roll.trace[1].value, roll.value);
+        assert_eq!(roll.explanation.matches('~').count(), 2);
+        assert!(roll
+            .explanation
+            .ends_with(&format!("; {} + 2 = {}", roll.value - 2, roll.value)));
+
+        // A lone roll needs no step to combine totals.
+        let roll = DiceExpr::parse("4d6cs>4")
+            .unwrap()
+            .roll(&mut rng())
+            .unwrap();
+        assert_eq!(roll.trace.len(), 1);
+        assert_eq!(roll.trace[0].expression, "4d6cs>4");
+        assert_eq!(roll.trace[0].value, roll.value);
+
+        let roll = DiceExpr::parse("7").unwrap().roll(&mut rng()).unwrap();
+        assert_eq!(roll.explanation, "7 = 7");
+
+        // Branches that were not taken are left as written.
+        let roll = DiceExpr::parse("(1d20) >= 1 ? 2d4 : 1d6")
+            .unwrap()
+            .roll(&mut rng())
+            .unwrap();
+        assert_eq!(roll.trace.len(), 3);
+        assert_eq!(
+            roll.trace[2].expression,
+            format!(
+                "{} >= 1 ? {} : 1d6",
+                roll.trace[0].value, roll.trace[1].value
+            )
+        );
+
+        let bindings = HashMap::from([("dex".to_string(), 3)]);
+        let roll = DiceExpr::parse("1d20 + @dex")
+            .unwrap()
+            .roll_with(&mut rng(), &bindings)
+            .unwrap();
+        assert_eq!(
+            roll.trace[1].expression,
+            format!("{} + 3", roll.trace[0].value)
+        );
+    }
+
+    #[test]
+    fn test_canonical_equality() {
+        let expr = DiceExpr::parse("d6+2d6+1").unwrap();
+        assert_eq!(expr, DiceExpr::parse("1 + 3d6").unwrap());
+        assert_ne!(expr, DiceExpr::parse("3d6 - 1").unwrap());
+        assert_eq!(expr.to_string(), "3d6 + 1");
+        assert_eq!(format!("{expr:#}"), "1d6 + 2d6 + 1");
+        assert_eq!(expr.normalize().normalize().to_string(), "3d6 + 1");
+
+        let unique: std::collections::HashSet<_> = ["2d8 + 4", "4 + 2d8", "1d8 + 1d8 + 2 + 2"]
+            .into_iter()
+            .map(|input| DiceExpr::parse(input).unwrap())
+            .collect();
+        assert_eq!(unique.len(), 1);
+    }
+
+    #[test]
+    fn test_expected_mean() {
+        assert_eq!(DiceExpr::parse("2d6").unwrap().mid().unwrap(), 6);
+        assert_close(DiceExpr::parse("3d6 + 3").unwrap().mean().unwrap(), 13.5);
+
+        let expr = DiceExpr::parse("d6!").unwrap();
+        assert!(matches!(
+            expr.expected_value(),
+            Err(Error::DistError(dist::Error::Intractable))
+        ));
+        assert!((expr.mean().unwrap() - 4.2).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_budget() {
+        let expr = DiceExpr::parse("999999999d100").unwrap();
+        assert!(matches!(
+            expr.roll(&mut rng()),
+            Err(Error::EvalError(eval::Error::TooManyDice { .. }))
+        ));
+        assert!(expr.max().is_err());
+
+        let budget = eval::Budget {
+            max_depth: 3,
+            ..Default::default()
+        };
+        let root = parser::parse("---1").unwrap();
+        let mut evaluator = eval::Evaluator::new(eval::Evaluation::Rand(rng())).with_budget(budget);
+        assert!(matches!(
+            evaluator.eval(&root),
+            Err(eval::Error::TooDeep { .. })
+        ));
+
+        let budget = eval::Budget {
+            max_dice: 5,
+            ..Default::default()
+        };
+        let root = parser::parse("6d6").unwrap();
+        let mut evaluator = eval::Evaluator::new(eval::Evaluation::Rand(rng())).with_budget(budget);
+        assert!(evaluator.eval(&root).is_err());
+        assert!(evaluator.eval(&parser::parse("5d6").unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_overflow() {
+        for input in [
+            "2147483647 + 1",
+            "-2147483647 - 2",
+            "65536 * 65536",
+            "(-2147483647 - 1) / -1",
+            "abs(-2147483647 - 1)",
+            "floor((-2147483647 - 1) / -1)",
+        ] {
+            let expr = DiceExpr::parse(input).unwrap();
+            assert!(
+                matches!(
+                    expr.roll(&mut rng()),
+                    Err(Error::EvalError(eval::Error::Overflow))
+                ),
+                "{input}"
+            );
+            assert!(
+                matches!(
+                    expr.distribution(),
+                    Err(Error::DistError(dist::Error::Overflow))
+                ),
+                "{input}"
+            );
+        }
+
+        let expr = DiceExpr::parse("2147483647 + 1d6").unwrap();
+        assert!(expr.roll(&mut rng()).is_err());
+        assert!(expr.distribution().is_err());
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        use lexer::{Span, TokenKind};
+        use parser::ErrorKind;
+
+        let error = parser::parse_batch("2d20khh1").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::InvalidWord);
+        assert_eq!(error.span, Span { start: 4, end: 7 });
+        assert_eq!(error.suggestion.as_deref(), Some("kh"));
+
+        let error = parser::parse_batch("d20 + mn(1, 2)").unwrap_err();
+        assert_eq!(error.span, Span { start: 6, end: 8 });
+        assert_eq!(error.suggestion.as_deref(), Some("min"));
+
+        let error = parser::parse_batch("2 +").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::UnexpectedEnd);
+        assert_eq!(error.span, Span { start: 3, end: 3 });
+        assert!(error.expected.iter().any(|token| token == "d"));
+
+        let error = parser::parse_batch("(1 + 2]").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::MismatchedParentheses);
+        assert_eq!(error.span, Span { start: 6, end: 7 });
+        assert_eq!(error.suggestion.as_deref(), Some(")"));
+
+        let error = parser::parse_batch("abs(1, 2)").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::InvalidCall);
+        assert_eq!(error.span, Span { start: 0, end: 9 });
+
+        let error = parser::parse_batch("200x(d20)").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::InvalidRepeat);
+        assert_eq!(error.span, Span { start: 0, end: 3 });
+
+        // A comparison after a roll counts successes, so comparing the total
+        // needs parentheses.
+        let error = parser::parse_batch("2d6 > 7").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::ImpossibleTarget);
+        assert_eq!(error.span, Span { start: 4, end: 5 });
+        assert_eq!(error.suggestion.as_deref(), Some("(2d6) > 7"));
+        assert!(DiceExpr::parse("(2d6) > 7").is_ok());
+
+        let error = parser::parse_batch("4dF = 2").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::ImpossibleTarget);
+        let error = parser::parse_batch("8d6cs<1").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::ImpossibleTarget);
+        assert_eq!(error.suggestion, None);
+        for input in ["2d6 > 5", "2d6 = 6", "d6! > 5", "d6!! > 7", "2d(3 * 2) > 7"] {
+            assert!(parser::parse_batch(input).is_ok(), "{input}");
+        }
+
+        // Deep nesting is rejected by the parser, whether or not the parser
+        // itself recurses to build it.
+        let deep = parser::MAX_NESTING + 1;
+        for input in [
+            format!("{}1", "-".repeat(deep)),
+            format!("{}1{}", "(".repeat(deep), ")".repeat(deep)),
+            format!("1{}", " + 1".repeat(parser::MAX_DEPTH)),
+            format!("4d6{}", "!!".repeat(deep)),
+        ] {
+            let error = parser::parse_batch(&input).unwrap_err();
+            assert_eq!(error.kind, ErrorKind::TooDeep, "{input}");
+        }
+        assert!(parser::parse(&format!("1{}", " + 1".repeat(100))).is_ok());
+
+        let kinds: Vec<_> = lexer::highlight("2d6 + @str $")
+            .into_iter()
+            .map(|token| token.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Integer,
+                TokenKind::Word,
+                TokenKind::Integer,
+                TokenKind::Operator,
+                TokenKind::Variable,
+                TokenKind::Error,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_explode_round_trip() {
+        assert_eq!(print("4d6!"), "4d6!");
+        assert_eq!(print("2d6!>=5"), "2d6!>=5");
+        assert_eq!(print("d10!1"), "1d10!=1");
+        assert_eq!(print("3d6!!"), "3d6!!");
+        assert_eq!(print("3d6!!<2kh2"), "3d6!!<2kh2");
+    }
+
+    #[test]
+    fn test_explode_adds_dice() {
+        let expr = DiceExpr::parse("20d6!").unwrap();
+        let mut rng = rng();
+
+        for _ in 0..20 {
+            let roll = expr.roll(&mut rng).unwrap();
+            let sixes = roll.dice.iter().filter(|d| d.result == 6).count();
+            let exploded = roll.dice.iter().filter(|d| d.exploded).count();
+
+            assert_eq!(sixes, exploded);
+            assert_eq!(roll.dice.len(), 20 + exploded);
+            assert_eq!(roll.value, roll.dice.iter().map(|d| d.result).sum::<i32>());
+        }
+    }
+
+    #[test]
+    fn test_explode_then_keep() {
+        let expr = DiceExpr::parse("4d6!>=4kh2").unwrap();
+        let mut rng = rng();
+
+        for _ in 0..20 {
+            let roll = expr.roll(&mut rng).unwrap();
+            let kept: Vec<_> = roll.dice.iter().filter(|d| d.keep).collect();
+
+            assert_eq!(kept.len(), 2);
+            assert_eq!(roll.value, kept.iter().map(|d| d.result).sum::<i32>());
+        }
+    }
+
+    #[test]
+    fn test_compound_explode() {
+        let expr = DiceExpr::parse("20d6!!").unwrap();
+        let mut rng = rng();
+
+        for _ in 0..20 {
+            let roll = expr.roll(&mut rng).unwrap();
+
+            assert_eq!(roll.dice.len(), 20);
+            for die in &roll.dice {
+                assert_eq!(die.exploded, die.result >= 6);
+                assert_ne!(die.result % 6, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_reroll_round_trip() {
+        assert_eq!(print("2d6ro<=2"), "2d6ro<=2");
+        assert_eq!(print("d20r1"), "1d20r=1");
+        assert_eq!(print("4d6r<3kh3"), "4d6r<3kh3");
+    }
+
+    #[test]
+    fn test_reroll_replaces_dice() {
+        let expr = DiceExpr::parse("10d4r<=2").unwrap();
+        let mut rng = rng();
+
+        for _ in 0..20 {
+            let roll = expr.roll(&mut rng).unwrap();
+            let kept: Vec<_> = roll.dice.iter().filter(|d| d.keep).collect();
+
+            assert_eq!(kept.len(), 10);
+            assert!(kept.iter().all(|d| d.result > 2));
+            assert!(roll.dice.iter().filter(|d| !d.keep).all(|d| d.result <= 2));
+            assert_eq!(roll.value, kept.iter().map(|d| d.result).sum::<i32>());
+        }
+    }
+
+    #[test]
+    fn test_reroll_then_keep() {
+        let expr = DiceExpr::parse("4d6r1kh3").unwrap();
+        let mut rng = rng();
+
+        for _ in 0..20 {
+            let roll = expr.roll(&mut rng).unwrap();
+            let kept: Vec<_> = roll.dice.iter().filter(|d| d.keep).collect();
+
+            assert_eq!(kept.len(), 3);
+            assert!(kept.iter().all(|d| d.result > 1));
+        }
+    }
+
+    #[test]
+    fn test_reroll_deterministic() {
+        let expr = DiceExpr::parse("d20r1").unwrap();
+        assert_eq!(expr.min().unwrap(), 2);
+        assert_eq!(expr.max().unwrap(), 20);
+
+        let expr = DiceExpr::parse("d20ro1").unwrap();
+        assert_eq!(expr.min().unwrap(), 1);
+
+        let expr = DiceExpr::parse("2d6r>=4").unwrap();
+        assert_eq!(expr.max().unwrap(), 6);
+        assert_eq!(expr.mid().unwrap(), 6);
+
+        let expr = DiceExpr::parse("2d6r<=3").unwrap();
+        assert_eq!(expr.min().unwrap(), 8);
+        assert_eq!(expr.mid().unwrap(), 10);
+    }
+
+    #[test]
+    fn test_count_round_trip() {
+        assert_eq!(print("8d6>=5"), "8d6cs>=5");
+        assert_eq!(print("8d6cs6"), "8d6cs=6");
+        assert_eq!(print("10d10cs>=8f1"), "10d10cs>=8f=1");
+        assert_eq!(print("10d10cs>=8 - f1"), "10d10cs>=8f=1");
+        assert_eq!(print("8d6!cs>=5 + 2"), "8d6!cs>=5 + 2");
+        assert!(DiceExpr::parse("2d6 - f1").is_err());
+    }
+
+    #[test]
+    fn test_count_successes() {
+        let expr = DiceExpr::parse("10d10cs>=8 - f1").unwrap();
+        let mut rng = rng();
+
+        for _ in 0..20 {
+            let roll = expr.roll(&mut rng).unwrap();
+            let successes = roll.dice.iter().filter(|d| d.result >= 8).count() as i32;
+            let failures = roll.dice.iter().filter(|d| d.result == 1).count() as i32;
+
+            assert_eq!(roll.value, successes - failures);
+            for die in &roll.dice {
+                let expected = match die.result {
+                    8.. => Some(Outcome::Success),
+                    1 => Some(Outcome::Failure),
+                    _ => None,
+                };
+                assert_eq!(die.outcome, expected);
+            }
+        }
+
+        let expr = DiceExpr::parse("8d6>=5").unwrap();
+        assert_eq!(expr.max().unwrap(), 8);
+        assert_eq!(expr.min().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_probability() {
+        let expr = DiceExpr::parse("d20adv+7").unwrap();
+        assert_close(
+            expr.probability(Comparison::GreaterOrEqual, 15).unwrap(),
+            351.0 / 400.0,
+        );
+
+        let expr = DiceExpr::parse("d20dis+7").unwrap();
+        assert_close(
+            expr.probability(Comparison::GreaterOrEqual, 15).unwrap(),
+            169.0 / 400.0,
+        );
+
+        let expr = DiceExpr::parse("d20").unwrap();
+        assert_close(expr.probability(Comparison::Less, 6).unwrap(), 0.25);
+        assert_close(expr.probability(Comparison::Equal, 21).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_mean() {
+        let distribution = DiceExpr::parse("2d6 + 3").unwrap().distribution().unwrap();
+        assert_close(distribution.mean(), 10.0);
+
+        let distribution = DiceExpr::parse("d20adv").unwrap().distribution().unwrap();
+        assert_close(distribution.mean(), 13.825);
+    }
+
+    #[test]
+    fn test_explode_is_bounded() {
+        let expr = DiceExpr::parse("d6!>=1").unwrap();
+        let roll = expr.roll(&mut rng()).unwrap();
+        assert_eq!(roll.dice.len(), 101);
+    }
+}
@@ -25,6 +25,118 @@ pub enum Selection {
 
     /// Reroll the previous expression and keep the lower total.
     Disadvantage,
+
+    /// Roll an additional die for each die that meets the target (or rolls
+    /// its maximum if no target is given). Additional dice may explode again.
+    Explode { target: Option<Target> },
+
+    /// Like [`Selection::Explode`], but additional dice are added to the die
+    /// that exploded rather than counted separately.
+    CompoundExplode { target: Option<Target> },
+
+    /// Discard and reroll each die that meets the target until it no longer
+    /// does.
+    Reroll { target: Target },
+
+    /// Discard and reroll each die that meets the target once, keeping the
+    /// new result regardless of its value.
+    RerollOnce { target: Target },
+}
+
+/// Comparison operators used to test individual dice against a target, or to
+/// compare the values of two expressions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Comparison {
+    /// The die is equal to the target.
+    Equal,
+
+    /// The die is less than the target.
+    Less,
+
+    /// The die is less than or equal to the target.
+    LessOrEqual,
+
+    /// The die is greater than the target.
+    Greater,
+
+    /// The die is greater than or equal to the target.
+    GreaterOrEqual,
+}
+
+/// Built-in functions that can be called in an expression, such as
+/// `max(1, d4 - 1)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Function {
+    /// The smallest of the arguments.
+    Min,
+
+    /// The largest of the arguments.
+    Max,
+
+    /// The argument rounded down. A division argument, as in `floor(7 / 2)`,
+    /// is rounded down rather than truncated.
+    Floor,
+
+    /// The argument rounded up. A division argument, as in `ceil(7 / 2)`, is
+    /// rounded up rather than truncated.
+    Ceil,
+
+    /// The argument rounded to the nearest integer. A division argument, as
+    /// in `round(7 / 2)`, is rounded with halves rounded up.
+    Round,
+
+    /// The absolute value of the argument.
+    Abs,
+}
+
+impl Function {
+    /// Every built-in function.
+    pub const ALL: [Function; 6] = [
+        Function::Min,
+        Function::Max,
+        Function::Floor,
+        Function::Ceil,
+        Function::Round,
+        Function::Abs,
+    ];
+
+    /// Returns the name used to call the function.
+    pub fn name(self) -> &'static str {
+        match self {
+            Function::Min => "min",
+            Function::Max => "max",
+            Function::Floor => "floor",
+            Function::Ceil => "ceil",
+            Function::Round => "round",
+            Function::Abs => "abs",
+        }
+    }
+
+    /// Returns the function called `name`, if there is one.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|function| function.name() == name)
+    }
+
+    /// Returns the least and greatest number of arguments the function
+    /// accepts.
+    pub fn arity(self) -> (usize, usize) {
+        match self {
+            Function::Min | Function::Max => (1, usize::MAX),
+            Function::Floor | Function::Ceil | Function::Round | Function::Abs => (1, 1),
+        }
+    }
+}
+
+/// A target that individual dice are compared against.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Target {
+    /// How dice are compared with the target value.
+    pub comparison: Comparison,
+
+    /// The target value.
+    pub value: Box<Node>,
 }
 
 /// A node in the syntax tree.
@@ -33,10 +145,17 @@ pub enum Node {
     /// Node that represents a literal integer value.
     Lit { value: i32 },
 
-    /// A node that represents rolling some number of particular dice.
+    /// A node that represents a variable such as `@dex`, which is resolved
+    /// when the expression is evaluated.
+    Var { name: String },
+
+    /// A node that represents rolling some number of particular dice. Fudge
+    /// dice have the faces -1, 0 and +1 instead of being numbered from 1 to
+    /// `sides`.
     Roll {
         count: Box<Node>,
         sides: Box<Node>,
+        fudge: bool,
         select: Option<Box<Node>>,
     },
 
@@ -46,6 +165,17 @@ pub enum Node {
         next: Option<Box<Node>>,
     },
 
+    /// A node that counts the dice kept by `roll` that meet the `success`
+    /// target, less those that meet the `failure` target.
+    Count {
+        roll: Box<Node>,
+        success: Target,
+        failure: Option<Target>,
+    },
+
+    /// A node that labels the value of `right`, such as its damage type.
+    Tag { label: String, right: Box<Node> },
+
     /// A node that represents the unary negation operation.
     Neg { right: Box<Node> },
 
@@ -60,4 +190,106 @@ pub enum Node {
 
     /// A node that represents the division operation.
     Div { left: Box<Node>, right: Box<Node> },
+
+    /// A node that compares the value of `left` with the value of `right`,
+    /// producing 1 if the comparison holds and 0 otherwise.
+    Compare {
+        comparison: Comparison,
+        left: Box<Node>,
+        right: Box<Node>,
+    },
+
+    /// A node that evaluates `then` if `condition` is nonzero or `otherwise`
+    /// if it is zero. Only the chosen branch is evaluated.
+    Cond {
+        condition: Box<Node>,
+        then: Box<Node>,
+        otherwise: Box<Node>,
+    },
+
+    /// A node that resolves an attack, evaluating `damage` if `attack` meets
+    /// `target` and producing 0 otherwise. A natural 20 on a d20 in `attack`
+    /// always hits and rolls `damage` as a critical hit, while a natural 1
+    /// always misses.
+    Hit {
+        attack: Box<Node>,
+        target: Box<Node>,
+        damage: Box<Node>,
+    },
+
+    /// A node that represents a call to a built-in function.
+    Call { function: Function, args: Vec<Node> },
+}
+
+impl Node {
+    /// Returns the nodes directly beneath this one in the tree, including
+    /// those within selections and targets.
+    pub fn children(&self) -> Vec<&Node> {
+        match self {
+            Node::Lit { .. } | Node::Var { .. } => vec![],
+            Node::Roll {
+                count,
+                sides,
+                select,
+                ..
+            } => [Some(count), Some(sides), select.as_ref()]
+                .into_iter()
+                .flatten()
+                .map(Box::as_ref)
+                .collect(),
+            Node::Select { selection, next } => {
+                let mut children = selection.children();
+                children.extend(next.as_deref());
+                children
+            }
+            Node::Count {
+                roll,
+                success,
+                failure,
+            } => {
+                let mut children = vec![roll.as_ref(), success.value.as_ref()];
+                children.extend(failure.as_ref().map(|failure| failure.value.as_ref()));
+                children
+            }
+            Node::Tag { right, .. } | Node::Neg { right } => vec![right],
+            Node::Add { left, right }
+            | Node::Sub { left, right }
+            | Node::Mul { left, right }
+            | Node::Div { left, right }
+            | Node::Compare { left, right, .. } => vec![left, right],
+            Node::Cond {
+                condition,
+                then,
+                otherwise,
+            } => vec![condition, then, otherwise],
+            Node::Hit {
+                attack,
+                target,
+                damage,
+            } => vec![attack, target, damage],
+            Node::Call { args, .. } => args.iter().collect(),
+        }
+    }
+}
+
+impl Selection {
+    /// Returns the nodes within the selection, such as the number of dice to
+    /// keep or the target to reroll.
+    pub fn children(&self) -> Vec<&Node> {
+        match self {
+            Selection::KeepLowest { count }
+            | Selection::KeepHighest { count }
+            | Selection::DropLowest { count }
+            | Selection::DropHighest { count } => count.as_deref().into_iter().collect(),
+            Selection::Advantage | Selection::Disadvantage => vec![],
+            Selection::Explode { target } | Selection::CompoundExplode { target } => target
+                .as_ref()
+                .map(|target| target.value.as_ref())
+                .into_iter()
+                .collect(),
+            Selection::Reroll { target } | Selection::RerollOnce { target } => {
+                vec![target.value.as_ref()]
+            }
+        }
+    }
 }
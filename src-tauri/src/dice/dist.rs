@@ -0,0 +1,649 @@
+// Copyright (c) 2025 Jonathon B. Cobb
+// Licensed under the MIT License
+
+//! This module computes the exact probability distribution of a dice
+//! expression by traversing an AST and convolving the distributions of its
+//! sub-expressions.
+//!
+//! Rolls without selections are treated as independent dice so that large
+//! pools can be convolved cheaply. Selections that depend on the dice as a
+//! whole, such as `kh` or `adv`, require the joint distribution of every die in
+//! the pool, which is enumerated as a distribution over sorted dice. If the
+//! number of states required grows too large, [`Error::Intractable`] is
+//! returned and the caller may fall back to sampling.
+
+use std::{
+    collections::{btree_map::Entry, BTreeMap},
+    error::Error as StdError,
+    fmt::{Display, Formatter, Result as FmtResult},
+    ops::RangeInclusive,
+};
+
+use super::ast::{Comparison, Function, Node, Selection, Target};
+use super::eval::{compare, divide, explodes, faces, MAX_EXPLOSIONS};
+
+/// The maximum number of states in any intermediate distribution.
+const MAX_STATES: usize = 100_000;
+
+/// Chains of explosions less likely than this are not followed any further.
+const MIN_PROBABILITY: f64 = 1e-12;
+
+/// A probability mass function mapping each possible value to its
+/// probability.
+pub type Pmf = BTreeMap<i32, f64>;
+
+/// A probability distribution over groups of dice, each sorted from highest
+/// to lowest.
+type Dice = BTreeMap<Vec<i32>, f64>;
+
+/// The dice rolled by a roll node after applying some of its selections.
+enum Pool {
+    /// `count` independent dice, each of which has become the group of dice
+    /// described by `group` (more than one if it exploded).
+    Independent { count: usize, group: Dice },
+
+    /// The joint distribution of every die in the pool.
+    Joint(Dice),
+}
+
+/// Possible errors that can occur while computing a distribution.
+#[derive(Debug)]
+pub enum Error {
+    /// A selection operation (`kh`, `dl`, etc.) involves selecting more dice
+    /// than may be available after rolling and applying previous selections.
+    InvalidSelection {
+        selection_size: usize,
+        pool_size: usize,
+    },
+
+    /// The expression may divide by zero.
+    DivideByZero,
+
+    /// A die was given fewer than one side.
+    InvalidDie { sides: i32 },
+
+    /// The distribution cannot be computed exactly within the limits on the
+    /// number of states.
+    Intractable,
+
+    /// An arithmetic operation may overflow.
+    Overflow,
+}
+
+/// Computes the exact probability distribution of the expression rooted at
+/// `node`.
+pub fn distribution(node: &Node) -> Result<Pmf, Error> {
+    let pmf = visit(node)?;
+    let total: f64 = pmf.values().sum();
+    Ok(pmf
+        .into_iter()
+        .map(|(value, p)| (value, p / total))
+        .collect())
+}
+
+fn visit(node: &Node) -> Result<Pmf, Error> {
+    match node {
+        Node::Lit { value } => Ok(Pmf::from([(*value, 1.0)])),
+        Node::Roll {
+            count,
+            sides,
+            fudge,
+            select,
+        } => {
+            let pool = roll(count, sides, *fudge, select.as_deref())?;
+            score(pool, |dice| dice.iter().sum())
+        }
+        Node::Count {
+            roll: node,
+            success,
+            failure,
+        } => {
+            let Node::Roll {
+                count,
+                sides,
+                fudge,
+                select,
+            } = node.as_ref()
+            else {
+                return Err(Error::Intractable);
+            };
+
+            let pool = roll(count, sides, *fudge, select.as_deref())?;
+            let success = target(success)?;
+            let failure = failure.as_ref().map(target).transpose()?;
+
+            score(pool, |dice| {
+                dice.iter()
+                    .map(|&die| {
+                        if compare(success.0, die, success.1) {
+                            1
+                        } else if failure
+                            .is_some_and(|(comparison, value)| compare(comparison, die, value))
+                        {
+                            -1
+                        } else {
+                            0
+                        }
+                    })
+                    .sum()
+            })
+        }
+        Node::Select { .. } | Node::Var { .. } => Err(Error::Intractable),
+        Node::Tag { right, .. } => visit(right),
+        Node::Neg { right } => visit(right)?
+            .into_iter()
+            .map(|(value, p)| Ok((value.checked_neg().ok_or(Error::Overflow)?, p)))
+            .collect(),
+        Node::Add { left, right } => binary(&visit(left)?, &visit(right)?, |l, r| {
+            l.checked_add(r).ok_or(Error::Overflow)
+        }),
+        Node::Sub { left, right } => binary(&visit(left)?, &visit(right)?, |l, r| {
+            l.checked_sub(r).ok_or(Error::Overflow)
+        }),
+        Node::Mul { left, right } => binary(&visit(left)?, &visit(right)?, |l, r| {
+            l.checked_mul(r).ok_or(Error::Overflow)
+        }),
+        Node::Div { left, right } => binary(&visit(left)?, &visit(right)?, |l, r| {
+            if r == 0 {
+                return Err(Error::DivideByZero);
+            }
+            l.checked_div(r).ok_or(Error::Overflow)
+        }),
+        Node::Compare {
+            comparison,
+            left,
+            right,
+        } => binary(&visit(left)?, &visit(right)?, |l, r| {
+            Ok(compare(*comparison, l, r) as i32)
+        }),
+        Node::Cond {
+            condition,
+            then,
+            otherwise,
+        } => {
+            // The condition and branches roll separate dice, so they are
+            // independent and the branches can be weighted by the condition.
+            let condition = visit(condition)?;
+            let total: f64 = condition.values().sum();
+            let p = condition
+                .into_iter()
+                .filter(|&(value, _)| value != 0)
+                .map(|(_, p)| p / total)
+                .sum::<f64>();
+
+            let mut pmf = Pmf::new();
+            for (branch, weight) in [(then, p), (otherwise, 1.0 - p)] {
+                if weight > 0.0 {
+                    for (value, q) in visit(branch)? {
+                        *pmf.entry(value).or_default() += weight * q;
+                    }
+                }
+            }
+            Ok(pmf)
+        }
+        // Natural 20s and 1s depend on the individual dice of the attack.
+        Node::Hit { .. } => Err(Error::Intractable),
+        Node::Call { function, args } => call(*function, args),
+    }
+}
+
+fn call(function: Function, args: &[Node]) -> Result<Pmf, Error> {
+    if let (Function::Floor | Function::Ceil | Function::Round, [Node::Div { left, right }]) =
+        (function, args)
+    {
+        return binary(&visit(left)?, &visit(right)?, |l, r| {
+            if r == 0 {
+                return Err(Error::DivideByZero);
+            }
+            divide(function, l, r).ok_or(Error::Overflow)
+        });
+    }
+
+    let mut args = args.iter().map(visit);
+    let Some(first) = args.next() else {
+        return Err(Error::Intractable);
+    };
+
+    match function {
+        Function::Min => args.try_fold(first?, |acc, arg| binary(&acc, &arg?, |l, r| Ok(l.min(r)))),
+        Function::Max => args.try_fold(first?, |acc, arg| binary(&acc, &arg?, |l, r| Ok(l.max(r)))),
+        Function::Floor | Function::Ceil | Function::Round => first,
+        Function::Abs => {
+            let mut pmf = Pmf::new();
+            for (value, p) in first? {
+                let value = value.checked_abs().ok_or(Error::Overflow)?;
+                *pmf.entry(value).or_default() += p;
+            }
+            Ok(pmf)
+        }
+    }
+}
+
+/// Evaluates a node that must have a single possible value, such as the
+/// count of a selection.
+fn constant(node: &Node) -> Result<i32, Error> {
+    let pmf = visit(node)?;
+    match (pmf.len(), pmf.keys().next()) {
+        (1, Some(&value)) => Ok(value),
+        _ => Err(Error::Intractable),
+    }
+}
+
+fn target(target: &Target) -> Result<(Comparison, i32), Error> {
+    Ok((target.comparison, constant(&target.value)?))
+}
+
+/// Rolls a pool of dice and applies each selection in the chain beginning at
+/// `select`.
+fn roll(count: &Node, sides: &Node, fudge: bool, select: Option<&Node>) -> Result<Pool, Error> {
+    let count = constant(count)?.max(0) as usize;
+    let sides = constant(sides)?;
+    if sides < 1 {
+        return Err(Error::InvalidDie { sides });
+    }
+
+    let faces = faces(sides, fudge);
+    if faces.clone().count() > MAX_STATES {
+        return Err(Error::Intractable);
+    }
+
+    let mut pool = Pool::Independent {
+        count,
+        group: uniform(faces.clone()),
+    };
+
+    let mut next = select;
+    while let Some(node) = next {
+        let Node::Select {
+            selection,
+            next: rest,
+        } = node
+        else {
+            return Err(Error::Intractable);
+        };
+
+        pool = apply(pool, faces.clone(), selection)?;
+        next = rest.as_deref();
+    }
+
+    Ok(pool)
+}
+
+fn apply(pool: Pool, faces: RangeInclusive<i32>, selection: &Selection) -> Result<Pool, Error> {
+    match selection {
+        Selection::KeepHighest { count }
+        | Selection::KeepLowest { count }
+        | Selection::DropHighest { count }
+        | Selection::DropLowest { count } => {
+            let count = match count {
+                Some(count) => constant(count)? as usize,
+                None => 1,
+            };
+
+            let mut result = Dice::new();
+            for (dice, p) in joint(pool)? {
+                if count > dice.len() {
+                    return Err(Error::InvalidSelection {
+                        selection_size: count,
+                        pool_size: dice.len(),
+                    });
+                }
+
+                let remaining = dice.len() - count;
+                let kept = match selection {
+                    Selection::KeepHighest { .. } => &dice[..count],
+                    Selection::KeepLowest { .. } => &dice[remaining..],
+                    Selection::DropHighest { .. } => &dice[count..],
+                    _ => &dice[..remaining],
+                };
+
+                *result.entry(kept.to_vec()).or_default() += p;
+            }
+
+            Ok(Pool::Joint(result))
+        }
+
+        Selection::Advantage | Selection::Disadvantage => {
+            let advantage = matches!(selection, Selection::Advantage);
+            let mut rerolls = BTreeMap::new();
+            let mut result = Dice::new();
+
+            for (old, p) in joint(pool)? {
+                let rerolls = match rerolls.entry(old.len()) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => entry.insert(joint(Pool::Independent {
+                        count: old.len(),
+                        group: uniform(faces.clone()),
+                    })?),
+                };
+
+                let old_total: i32 = old.iter().sum();
+                for (new, q) in rerolls.iter() {
+                    let new_total: i32 = new.iter().sum();
+                    let kept = if (new_total > old_total) == advantage {
+                        new
+                    } else {
+                        &old
+                    };
+
+                    *result.entry(kept.clone()).or_default() += p * q;
+                }
+
+                if result.len() > MAX_STATES {
+                    return Err(Error::Intractable);
+                }
+            }
+
+            Ok(Pool::Joint(result))
+        }
+
+        Selection::Explode { target: goal } | Selection::CompoundExplode { target: goal } => {
+            let goal = goal.as_ref().map(target).transpose()?;
+            let compound = matches!(selection, Selection::CompoundExplode { .. });
+            let chain = explosions(faces.clone(), goal)?;
+
+            each_die(pool, |die| {
+                if !explodes(goal, *faces.end(), die) {
+                    return Ok(Dice::from([(vec![die], 1.0)]));
+                }
+
+                let dice = combine(&Dice::from([(vec![die], 1.0)]), &chain)?;
+                if !compound {
+                    return Ok(dice);
+                }
+
+                let mut result = Dice::new();
+                for (dice, p) in dice {
+                    *result.entry(vec![dice.iter().sum()]).or_default() += p;
+                }
+                Ok(result)
+            })
+        }
+
+        Selection::Reroll { target: goal } | Selection::RerollOnce { target: goal } => {
+            let (comparison, value) = target(goal)?;
+            let once = matches!(selection, Selection::RerollOnce { .. });
+
+            let remaining: Vec<i32> = faces
+                .clone()
+                .filter(|&face| once || !compare(comparison, face, value))
+                .collect();
+            let replacement = if remaining.is_empty() {
+                uniform(faces)
+            } else {
+                remaining
+                    .iter()
+                    .map(|&face| (vec![face], 1.0 / remaining.len() as f64))
+                    .collect()
+            };
+
+            each_die(pool, |die| {
+                if compare(comparison, die, value) {
+                    Ok(replacement.clone())
+                } else {
+                    Ok(Dice::from([(vec![die], 1.0)]))
+                }
+            })
+        }
+    }
+}
+
+/// Computes the distribution of the dice added by a die that explodes,
+/// following chains of explosions until they become negligible.
+fn explosions(
+    faces: RangeInclusive<i32>,
+    target: Option<(Comparison, i32)>,
+) -> Result<Dice, Error> {
+    let mut chain = Dice::from([(vec![], 1.0)]);
+    let p = 1.0 / faces.clone().count() as f64;
+
+    for _ in 0..MAX_EXPLOSIONS {
+        let mut next = Dice::new();
+        for face in faces.clone() {
+            let tail = if explodes(target, *faces.end(), face) {
+                combine(&Dice::from([(vec![face], p)]), &chain)?
+            } else {
+                Dice::from([(vec![face], p)])
+            };
+
+            for (dice, q) in tail {
+                if q >= MIN_PROBABILITY {
+                    *next.entry(dice).or_default() += q;
+                }
+            }
+        }
+
+        if next == chain {
+            break;
+        }
+        chain = next;
+    }
+
+    if 1.0 - chain.values().sum::<f64>() > 1e-9 {
+        return Err(Error::Intractable);
+    }
+
+    Ok(chain)
+}
+
+/// The distribution of a single die with the given faces.
+fn uniform(faces: RangeInclusive<i32>) -> Dice {
+    let p = 1.0 / faces.clone().count() as f64;
+    faces.map(|face| (vec![face], p)).collect()
+}
+
+/// Computes the joint distribution of every die in a pool.
+fn joint(pool: Pool) -> Result<Dice, Error> {
+    match pool {
+        Pool::Joint(dice) => Ok(dice),
+        Pool::Independent { count, group } => {
+            let mut dice = Dice::from([(vec![], 1.0)]);
+            for _ in 0..count {
+                dice = combine(&dice, &group)?;
+            }
+            Ok(dice)
+        }
+    }
+}
+
+/// Replaces each die in a pool independently with a distribution over the
+/// dice it becomes.
+fn each_die<F>(pool: Pool, f: F) -> Result<Pool, Error>
+where
+    F: Fn(i32) -> Result<Dice, Error>,
+{
+    let replace = |dice: &[i32]| -> Result<Dice, Error> {
+        let mut result = Dice::from([(vec![], 1.0)]);
+        for &die in dice {
+            result = combine(&result, &f(die)?)?;
+        }
+        Ok(result)
+    };
+
+    let mix = |dice: Dice| -> Result<Dice, Error> {
+        let mut result = Dice::new();
+        for (dice, p) in dice {
+            for (replaced, q) in replace(&dice)? {
+                *result.entry(replaced).or_default() += p * q;
+            }
+
+            if result.len() > MAX_STATES {
+                return Err(Error::Intractable);
+            }
+        }
+        Ok(result)
+    };
+
+    match pool {
+        Pool::Independent { count, group } => Ok(Pool::Independent {
+            count,
+            group: mix(group)?,
+        }),
+        Pool::Joint(dice) => Ok(Pool::Joint(mix(dice)?)),
+    }
+}
+
+/// Computes the distribution of two independent groups of dice rolled
+/// together.
+fn combine(left: &Dice, right: &Dice) -> Result<Dice, Error> {
+    let mut result = Dice::new();
+
+    for (l, p) in left {
+        for (r, q) in right {
+            let mut dice = [l.as_slice(), r.as_slice()].concat();
+            dice.sort_unstable_by(|a, b| b.cmp(a));
+            *result.entry(dice).or_default() += p * q;
+        }
+
+        if result.len() > MAX_STATES {
+            return Err(Error::Intractable);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Computes the distribution of a value derived from the dice in a pool.
+fn score<F>(pool: Pool, f: F) -> Result<Pmf, Error>
+where
+    F: Fn(&[i32]) -> i32,
+{
+    match pool {
+        Pool::Independent { count, group } => {
+            let mut base = Pmf::new();
+            for (dice, p) in group {
+                *base.entry(f(&dice)).or_default() += p;
+            }
+
+            // Sum `count` independent copies by repeated squaring.
+            let mut result = Pmf::from([(0, 1.0)]);
+            let mut count = count;
+            while count > 0 {
+                if count & 1 == 1 {
+                    result = binary(&result, &base, |l, r| {
+                        l.checked_add(r).ok_or(Error::Overflow)
+                    })?;
+                }
+
+                count >>= 1;
+                if count > 0 {
+                    base = binary(&base, &base, |l, r| l.checked_add(r).ok_or(Error::Overflow))?;
+                }
+            }
+
+            Ok(result)
+        }
+        Pool::Joint(dice) => {
+            let mut result = Pmf::new();
+            for (dice, p) in dice {
+                *result.entry(f(&dice)).or_default() += p;
+            }
+            Ok(result)
+        }
+    }
+}
+
+/// Computes the distribution of a binary operation over two independent
+/// distributions.
+fn binary<F>(left: &Pmf, right: &Pmf, op: F) -> Result<Pmf, Error>
+where
+    F: Fn(i32, i32) -> Result<i32, Error>,
+{
+    let mut result = Pmf::new();
+
+    for (&l, p) in left {
+        for (&r, q) in right {
+            *result.entry(op(l, r)?).or_default() += p * q;
+        }
+
+        if result.len() > MAX_STATES {
+            return Err(Error::Intractable);
+        }
+    }
+
+    Ok(result)
+}
+
+impl StdError for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Error::InvalidSelection {
+                selection_size,
+                pool_size,
+            } => write!(
+                f,
+                "Cannot select {} dice from a pool of {}",
+                selection_size, pool_size
+            ),
+            Error::DivideByZero => write!(f, "Division by zero"),
+            Error::InvalidDie { sides } => write!(f, "Invalid die: d{sides}"),
+            Error::Intractable => write!(f, "Expression is too complex to analyze exactly"),
+            Error::Overflow => write!(f, "Result may be too large"),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::dice::parser;
+
+    pub(crate) fn probabilities(input: &str) -> Vec<(i32, f64)> {
+        distribution(&parser::parse(input).unwrap())
+            .unwrap()
+            .into_iter()
+            .collect()
+    }
+
+    pub(crate) fn probability(input: &str, value: i32) -> f64 {
+        probabilities(input)
+            .into_iter()
+            .find(|&(v, _)| v == value)
+            .map_or(0.0, |(_, p)| p)
+    }
+
+    pub(crate) fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn test_distribution_sums() {
+        let outcomes = probabilities("2d6 + 1");
+        assert_eq!(outcomes.len(), 11);
+        assert_close(probability("2d6 + 1", 8), 6.0 / 36.0);
+        assert_close(outcomes.iter().map(|(_, p)| p).sum(), 1.0);
+    }
+
+    #[test]
+    fn test_distribution_selections() {
+        assert_close(probability("2d20kh1", 20), 39.0 / 400.0);
+        assert_close(probability("d20adv", 20), 39.0 / 400.0);
+        assert_close(probability("d20dis", 1), 39.0 / 400.0);
+        assert_close(probability("4d6kh3", 18), 21.0 / 1296.0);
+        assert_close(probability("4d6kh3", 3), 1.0 / 1296.0);
+        assert_close(probability("d20r1", 1), 0.0);
+        assert_close(probability("d20ro1", 1), 1.0 / 400.0);
+        assert_close(probability("8d6>=5", 8), (1.0f64 / 3.0).powi(8));
+    }
+
+    #[test]
+    fn test_distribution_explode() {
+        assert_close(probability("d6!", 6), 0.0);
+        assert_close(probability("d6!", 7), 1.0 / 36.0);
+        assert_close(probability("d6!", 13), 1.0 / 216.0);
+        assert_close(probability("d6!!", 7), 1.0 / 36.0);
+        assert_close(probability("2d6!kl1", 1), 0.36);
+    }
+
+    #[test]
+    fn test_distribution_errors() {
+        let distribution = |input: &str| distribution(&parser::parse(input).unwrap());
+        assert!(distribution("d6 / (d4 - 1)").is_err());
+        assert!(distribution("2d6kh3").is_err());
+    }
+}
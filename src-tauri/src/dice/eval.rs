@@ -5,16 +5,40 @@
 //! AST and returns the result of the expression.
 
 use std::{
+    collections::{BTreeMap, HashMap},
     error::Error as StdError,
     fmt::{Display, Formatter, Result as FmtResult},
-    ops::Range,
+    ops::{Range, RangeInclusive},
 };
 
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
-use super::ast::{Node, Selection};
+use super::ast::{Comparison, Function, Node, Selection, Target};
+use super::parser;
+
+/// The maximum number of additional dice a single die may produce by exploding.
+pub(super) const MAX_EXPLOSIONS: usize = 100;
+
+/// The maximum number of times a single die may be rerolled.
+const MAX_REROLLS: usize = 100;
+
+/// Limits on the work an evaluation may do, so that an expression such as
+/// `999999999d100` fails quickly instead of freezing the application.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Budget {
+    /// The maximum number of dice that may be rolled in total, including
+    /// rerolls, explosions and the extra dice of critical hits.
+    pub max_dice: usize,
+
+    /// The maximum number of additional dice a single die may produce by
+    /// exploding. Explosions stop at this depth rather than failing.
+    pub max_explosions: usize,
+
+    /// The maximum depth of nested nodes in the expression.
+    pub max_depth: usize,
+}
 
 /// Possible ways to evaluate dice rolls.
 pub enum Evaluation<TRng: Rng> {
@@ -30,32 +54,168 @@ pub enum Evaluation<TRng: Rng> {
 
     /// Evaluate the expression as if all dice rolls were the highest possible.
     Max,
+
+    /// Randomly generate each die roll for a critical hit, which adds extra
+    /// dice to every roll (but not to flat modifiers) according to the
+    /// evaluator's critical rule.
+    Critical(TRng),
+}
+
+/// Ways of rolling the extra dice of a critical hit.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Critical {
+    /// Roll twice as many dice.
+    #[default]
+    DoubleDice,
+
+    /// Roll the dice as usual and add the highest possible roll of the same
+    /// dice.
+    MaxPlusRoll,
 }
 
 /// A single die roll.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
 #[ts(export)]
 pub struct DieRoll {
-    /// The number of sides on the die. May be 4, 6, 8, 10, 12, 20, or 100.
+    /// The number of sides on the die. Fudge dice have three sides.
     pub sides: i32,
 
+    /// Whether the die is a Fudge die with the faces -1, 0 and +1.
+    #[serde(default)]
+    pub fudge: bool,
+
     /// The result of the roll.
     pub result: i32,
 
     /// Whether the roll was kept or discarded during a selection operation.
     pub keep: bool,
+
+    /// Whether the die was rolled because another die exploded or, for
+    /// compounding explosions, whether the result includes additional rolls.
+    #[serde(default)]
+    pub exploded: bool,
+
+    /// Whether the die counted as a success or failure when counting
+    /// successes, or `None` if it was not counted either way.
+    #[serde(default)]
+    pub outcome: Option<Outcome>,
+}
+
+/// The dice of a single roll in an expression and the total they contributed,
+/// which is the count of successes if the roll counted them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PoolTotal {
+    /// The range of the roll's dice in the evaluator's rolls, including any
+    /// that were discarded.
+    pub dice: Range<usize>,
+
+    /// The total of the kept dice, or the count of successes.
+    pub total: i32,
+}
+
+/// A branch taken during evaluation by a conditional or an attack.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "camelCase")]
+pub enum Branch {
+    /// The condition of a conditional was nonzero.
+    Then,
+
+    /// The condition of a conditional was zero.
+    Else,
+
+    /// The attack met its target.
+    Hit,
+
+    /// The attack missed its target or rolled a natural 1.
+    Miss,
+
+    /// The attack rolled a natural 20, so damage was rolled as a critical hit.
+    Critical,
+}
+
+/// How a single die contributed to a count of successes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "camelCase")]
+pub enum Outcome {
+    /// The die met the success target and added one to the count.
+    Success,
+
+    /// The die met the failure target and subtracted one from the count.
+    Failure,
+}
+
+/// Resolves the values of variables such as `@dex` during evaluation.
+pub trait Resolver {
+    /// Returns the value bound to the variable `name` (without the leading
+    /// `@`), or `None` if the variable is not bound.
+    fn resolve(&self, name: &str) -> Option<i32>;
+}
+
+impl Resolver for HashMap<String, i32> {
+    fn resolve(&self, name: &str) -> Option<i32> {
+        self.get(name).copied()
+    }
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Self {
+            max_dice: 10_000,
+            max_explosions: MAX_EXPLOSIONS,
+            max_depth: parser::MAX_DEPTH,
+        }
+    }
 }
 
 /// An implementation of the `Visitor` trait that evaluates each node in the AST
 /// using a stack and returns the result of the expression along with the
 /// individual die rolls.
-pub struct Evaluator<TRng: Rng> {
+pub struct Evaluator<'r, TRng: Rng> {
     /// The rolls made during the evaluation.
     pub rolls: Vec<DieRoll>,
 
     /// The evaluation strategy to use.
     evaluation: Evaluation<TRng>,
 
+    /// The subtotal of each label in the expression, such as a damage type.
+    pub breakdown: BTreeMap<String, i32>,
+
+    /// The value of each variable resolved during the evaluation.
+    pub variables: BTreeMap<String, i32>,
+
+    /// The branches taken by conditionals and attacks, in the order they were
+    /// evaluated.
+    pub branches: Vec<Branch>,
+
+    /// The dice and total of each roll, in the order the rolls were completed.
+    pub pools: Vec<PoolTotal>,
+
+    /// Resolves variables in the expression, if any are bound.
+    resolver: Option<&'r dyn Resolver>,
+
+    /// How to roll the extra dice of a critical hit.
+    critical_rule: Critical,
+
+    /// Limits on the work the evaluation may do.
+    budget: Budget,
+
+    /// The number of dice rolled so far, which counts against the budget.
+    dice_rolled: usize,
+
+    /// The depth of the node currently being visited.
+    depth: usize,
+
+    /// Whether the node currently being visited is subtracted from the total,
+    /// so that the labels within it count against their subtotals.
+    negated: bool,
+
+    /// Whether dice are currently being rolled for a critical hit, either
+    /// because the whole expression is or because an attack rolled a natural
+    /// 20.
+    critical: bool,
+
     /// A stack of intermediate results. Once the traversal is complete, the
     /// stack should contain a single value representing the result of the
     /// expression.
@@ -80,42 +240,142 @@ pub enum Error {
     /// An attempt was made to divide by zero.
     DivideByZero,
 
+    /// A variable was not bound by the resolver.
+    UnboundVariable(String),
+
+    /// A die was given fewer than one side.
+    InvalidDie { sides: i32 },
+
     /// The stack was empty when an operation expected a value.
     StackUnderflow,
+
+    /// An arithmetic operation overflowed.
+    Overflow,
+
+    /// The expression would roll more dice than the budget allows.
+    TooManyDice { max_dice: usize },
+
+    /// The expression is nested more deeply than the budget allows.
+    TooDeep { max_depth: usize },
 }
 
-impl<TRng: Rng> Evaluator<TRng> {
+impl<'r, TRng: Rng> Evaluator<'r, TRng> {
     pub fn new(evaluation: Evaluation<TRng>) -> Self {
         Self {
+            critical: matches!(evaluation, Evaluation::Critical(_)),
             evaluation,
+            resolver: None,
+            critical_rule: Critical::default(),
+            budget: Budget::default(),
+            dice_rolled: 0,
+            depth: 0,
+            negated: false,
             rolls: Vec::new(),
+            breakdown: BTreeMap::new(),
+            variables: BTreeMap::new(),
+            branches: Vec::new(),
+            pools: Vec::new(),
             results: Vec::new(),
             dice_pools: Vec::new(),
         }
     }
 
+    /// Creates an evaluator that resolves variables using `resolver`.
+    pub fn with_resolver(evaluation: Evaluation<TRng>, resolver: &'r dyn Resolver) -> Self {
+        Self {
+            resolver: Some(resolver),
+            ..Self::new(evaluation)
+        }
+    }
+
+    /// Sets how to roll the extra dice of a critical hit.
+    pub fn with_critical_rule(self, critical_rule: Critical) -> Self {
+        Self {
+            critical_rule,
+            ..self
+        }
+    }
+
+    /// Sets limits on the work the evaluation may do.
+    pub fn with_budget(self, budget: Budget) -> Self {
+        Self { budget, ..self }
+    }
+
+    /// Returns the number of dice rolled by the most recent evaluation,
+    /// including any rerolled or added by explosions.
+    pub fn dice_rolled(&self) -> usize {
+        self.dice_rolled
+    }
+
     pub fn eval(&mut self, node: &Node) -> Result<i32, Error> {
         self.rolls.clear();
+        self.breakdown.clear();
+        self.variables.clear();
+        self.branches.clear();
+        self.pools.clear();
+        self.results.clear();
+        self.dice_pools.clear();
+        self.dice_rolled = 0;
+        self.depth = 0;
+        self.negated = false;
+        self.critical = matches!(self.evaluation, Evaluation::Critical(_));
         self.visit(node)?;
         self.results.pop().ok_or(Error::StackUnderflow)
     }
 
     fn visit(&mut self, node: &Node) -> Result<(), Error> {
+        if self.depth >= self.budget.max_depth {
+            return Err(Error::TooDeep {
+                max_depth: self.budget.max_depth,
+            });
+        }
+
+        self.depth += 1;
+        let result = self.visit_node(node);
+        self.depth -= 1;
+        result
+    }
+
+    fn visit_node(&mut self, node: &Node) -> Result<(), Error> {
         match node {
             Node::Lit { value } => self.lit(*value),
+            Node::Var { name } => self.var(name),
             Node::Roll {
                 count,
                 sides,
+                fudge,
                 select,
-            } => self.roll(&count, &sides, select.as_ref().map(|n| n.as_ref())),
+            } => self.roll(&count, &sides, *fudge, select.as_ref().map(|n| n.as_ref())),
             Node::Select { selection, next } => {
                 self.select(selection, next.as_ref().map(|n| n.as_ref()))
             }
+            Node::Count {
+                roll,
+                success,
+                failure,
+            } => self.count(roll, success, failure.as_ref()),
+            Node::Tag { label, right } => self.tag(label, right.as_ref()),
             Node::Neg { right } => self.neg(right.as_ref()),
             Node::Add { left, right } => self.add(left.as_ref(), right.as_ref()),
             Node::Sub { left, right } => self.sub(left.as_ref(), right.as_ref()),
             Node::Mul { left, right } => self.mul(left.as_ref(), right.as_ref()),
             Node::Div { left, right } => self.div(left.as_ref(), right.as_ref()),
+            Node::Compare {
+                comparison,
+                left,
+                right,
+            } => self.compare(*comparison, left.as_ref(), right.as_ref()),
+            Node::Cond {
+                condition,
+                then,
+                otherwise,
+            } => self.cond(condition.as_ref(), then.as_ref(), otherwise.as_ref()),
+            Node::Hit {
+                attack,
+                target,
+                damage,
+            } => self.hit(attack.as_ref(), target.as_ref(), damage.as_ref()),
+            Node::Call { function, args } => self.call(*function, args),
         }
     }
 
@@ -124,7 +384,22 @@ impl<TRng: Rng> Evaluator<TRng> {
         Ok(())
     }
 
-    fn roll(&mut self, count: &Node, sides: &Node, select: Option<&Node>) -> Result<(), Error> {
+    fn var(&mut self, name: &str) -> Result<(), Error> {
+        let Some(value) = self.resolver.and_then(|resolver| resolver.resolve(name)) else {
+            return Err(Error::UnboundVariable(name.to_string()));
+        };
+        self.variables.insert(name.to_string(), value);
+        self.results.push(value);
+        Ok(())
+    }
+
+    fn roll(
+        &mut self,
+        count: &Node,
+        sides: &Node,
+        fudge: bool,
+        select: Option<&Node>,
+    ) -> Result<(), Error> {
         self.visit(count)?;
         let Some(count) = self.results.pop() else {
             return Err(Error::StackUnderflow);
@@ -135,36 +410,56 @@ impl<TRng: Rng> Evaluator<TRng> {
             return Err(Error::StackUnderflow);
         };
 
-        for _ in 0..count {
-            let roll = match &mut self.evaluation {
-                Evaluation::Rand(rng) => rng.gen_range(1..sides + 1),
-                Evaluation::Min => 1,
-                Evaluation::Mid => sides / 2,
-                Evaluation::Max => sides,
+        if sides < 1 {
+            return Err(Error::InvalidDie { sides });
+        }
+
+        let count = count.max(0) as usize;
+        let (rolled, maximized) = match (self.critical, self.critical_rule) {
+            (true, Critical::DoubleDice) => (count.saturating_mul(2), 0),
+            (true, Critical::MaxPlusRoll) => (count, count),
+            (false, _) => (count, 0),
+        };
+        self.spend(rolled.saturating_add(maximized))?;
+
+        let start = self.rolls.len();
+        for i in 0..rolled + maximized {
+            let faces = faces(sides, fudge);
+            let roll = if i < rolled {
+                self.roll_die(faces)
+            } else {
+                *faces.end()
             };
 
             self.rolls.push(DieRoll {
                 sides,
+                fudge,
                 result: roll,
                 keep: true,
+                exploded: false,
+                outcome: None,
             });
         }
 
-        let pool = self.rolls.len() - count as usize..self.rolls.len();
+        let mut pool = start..self.rolls.len();
         if let Some(select) = &select {
-            self.dice_pools.push(pool.clone());
+            self.dice_pools.push(pool);
             self.visit(select)?;
-            self.dice_pools.pop();
+            pool = self.dice_pools.pop().ok_or(Error::StackUnderflow)?;
         }
 
         self.rolls[pool.start..pool.end].sort_unstable_by(|a, b| b.result.cmp(&a.result));
 
-        let total = self
-            .rolls
+        let total = self.rolls[start..]
             .iter()
-            .map(|r| if r.keep { r.result } else { 0 })
-            .sum();
+            .filter(|r| r.keep)
+            .try_fold(0i32, |total, r| total.checked_add(r.result))
+            .ok_or(Error::Overflow)?;
 
+        self.pools.push(PoolTotal {
+            dice: start..self.rolls.len(),
+            total,
+        });
         self.results.push(total);
 
         Ok(())
@@ -235,27 +530,30 @@ impl<TRng: Rng> Evaluator<TRng> {
 
             Selection::Advantage | Selection::Disadvantage => {
                 // Reroll the current pool and select the highest/lowest total of the two rolls
+                self.spend(pool.len())?;
                 for i in pool.clone() {
-                    let sides = self.rolls[i].sides;
-                    let roll = match &mut self.evaluation {
-                        Evaluation::Rand(rng) => rng.gen_range(1..sides + 1),
-                        Evaluation::Min => 1,
-                        Evaluation::Mid => sides / 2,
-                        Evaluation::Max => sides,
-                    };
+                    let roll = self.roll_die(self.rolls[i].faces());
 
                     self.rolls.push(DieRoll {
-                        sides,
                         result: roll,
                         keep: true,
+                        exploded: false,
+                        outcome: None,
+                        ..self.rolls[i]
                     });
                 }
 
                 let old = pool.start..pool.end;
                 let new = self.rolls.len() - pool.len()..self.rolls.len();
 
-                let total_old: i32 = self.rolls[old.clone()].iter().map(|r| r.result).sum();
-                let total_new: i32 = self.rolls[new.clone()].iter().map(|r| r.result).sum();
+                let total_old: i64 = self.rolls[old.clone()]
+                    .iter()
+                    .map(|r| r.result as i64)
+                    .sum();
+                let total_new: i64 = self.rolls[new.clone()]
+                    .iter()
+                    .map(|r| r.result as i64)
+                    .sum();
                 let kept = if (total_new > total_old) == matches!(selection, Selection::Advantage) {
                     for roll in old {
                         self.rolls[roll].keep = false
@@ -276,15 +574,291 @@ impl<TRng: Rng> Evaluator<TRng> {
 
                 Ok(())
             }
+
+            Selection::Explode { target } | Selection::CompoundExplode { target } => {
+                let target = match target {
+                    Some(target) => {
+                        self.visit(&target.value)?;
+                        let value = self.results.pop().ok_or(Error::StackUnderflow)?;
+                        Some((target.comparison, value))
+                    }
+                    None => None,
+                };
+
+                // Deterministic evaluations would explode indefinitely, so
+                // explosions only apply to random rolls.
+                if self.is_random() {
+                    if matches!(selection, Selection::CompoundExplode { .. }) {
+                        self.compound_explode(pool, target)?;
+                    } else {
+                        self.explode(pool, target)?;
+                    }
+                }
+
+                // The exploded pool replaces the current one, so subsequent
+                // selections operate on it directly.
+                if let Some(next) = &next {
+                    self.visit(next)?;
+                }
+
+                Ok(())
+            }
+
+            Selection::Reroll { target } | Selection::RerollOnce { target } => {
+                self.visit(&target.value)?;
+                let value = self.results.pop().ok_or(Error::StackUnderflow)?;
+                let once = matches!(selection, Selection::RerollOnce { .. });
+                let limit = if once { 1 } else { MAX_REROLLS };
+
+                let mut discarded = Vec::new();
+                let mut live = Vec::with_capacity(pool.len());
+                for i in pool {
+                    let mut die = self.rolls[i].clone();
+                    let mut rerolls = 0;
+
+                    while rerolls < limit && compare(target.comparison, die.result, value) {
+                        self.spend(1)?;
+                        discarded.push(DieRoll {
+                            keep: false,
+                            ..die.clone()
+                        });
+                        die.result = if once {
+                            self.roll_die(die.faces())
+                        } else {
+                            self.reroll_die(die.faces(), target.comparison, value)
+                        };
+                        rerolls += 1;
+                    }
+
+                    live.push(die);
+                }
+
+                // Rerolled dice are moved out of the pool so that subsequent
+                // selections only consider their replacements.
+                self.replace_pool(discarded, live)?;
+
+                if let Some(next) = &next {
+                    self.visit(next)?;
+                }
+
+                Ok(())
+            }
         }
     }
 
-    fn neg(&mut self, right: &Node) -> Result<(), Error> {
+    /// Rolls additional dice for each die in `pool` that meets `target` (or
+    /// rolled its maximum if there is no target). Each additional die is
+    /// placed after the die that caused it.
+    fn explode(
+        &mut self,
+        pool: Range<usize>,
+        target: Option<(Comparison, i32)>,
+    ) -> Result<(), Error> {
+        let mut dice = Vec::with_capacity(pool.len());
+
+        for i in pool {
+            let die = self.rolls[i].clone();
+            let faces = die.faces();
+            let mut last = die.result;
+            let mut count = 0;
+            dice.push(die.clone());
+
+            while count < self.budget.max_explosions && explodes(target, *faces.end(), last) {
+                self.spend(1)?;
+                last = self.roll_die(faces.clone());
+                count += 1;
+                dice.push(DieRoll {
+                    result: last,
+                    keep: true,
+                    exploded: true,
+                    outcome: None,
+                    ..die
+                });
+            }
+        }
+
+        self.replace_pool(Vec::new(), dice)
+    }
+
+    /// Like [`Self::explode`], but adds each additional roll to the result of
+    /// the die that exploded instead of inserting new dice.
+    fn compound_explode(
+        &mut self,
+        pool: Range<usize>,
+        target: Option<(Comparison, i32)>,
+    ) -> Result<(), Error> {
+        for i in pool {
+            let faces = self.rolls[i].faces();
+            let mut last = self.rolls[i].result;
+            let mut count = 0;
+
+            while count < self.budget.max_explosions && explodes(target, *faces.end(), last) {
+                self.spend(1)?;
+                last = self.roll_die(faces.clone());
+                count += 1;
+                self.rolls[i].result = self.rolls[i]
+                    .result
+                    .checked_add(last)
+                    .ok_or(Error::Overflow)?;
+                self.rolls[i].exploded = true;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the dice in the current pool with `discarded` followed by
+    /// `live` and narrows the current pool to the `live` dice. Every other pool
+    /// on the stack is adjusted for the change in the number of dice.
+    fn replace_pool(&mut self, discarded: Vec<DieRoll>, live: Vec<DieRoll>) -> Result<(), Error> {
+        let pool = self.dice_pools.pop().ok_or(Error::StackUnderflow)?;
+        let added = discarded.len() + live.len() - pool.len();
+        let start = pool.start + discarded.len();
+        let end = start + live.len();
+
+        self.rolls
+            .splice(pool.clone(), discarded.into_iter().chain(live));
+
+        for other in self.dice_pools.iter_mut() {
+            if other.start <= pool.start && other.end >= pool.end {
+                other.end += added;
+            } else if other.start >= pool.end {
+                other.start += added;
+                other.end += added;
+            }
+        }
+
+        self.dice_pools.push(start..end);
+        Ok(())
+    }
+
+    /// Counts `dice` against the budget before they are rolled, failing if the
+    /// budget would be exceeded.
+    fn spend(&mut self, dice: usize) -> Result<(), Error> {
+        let max_dice = self.budget.max_dice;
+        match self.dice_rolled.checked_add(dice) {
+            Some(total) if total <= max_dice => {
+                self.dice_rolled = total;
+                Ok(())
+            }
+            _ => Err(Error::TooManyDice { max_dice }),
+        }
+    }
+
+    /// Returns whether dice are rolled randomly rather than deterministically.
+    fn is_random(&self) -> bool {
+        matches!(
+            self.evaluation,
+            Evaluation::Rand(_) | Evaluation::Critical(_)
+        )
+    }
+
+    fn roll_die(&mut self, faces: RangeInclusive<i32>) -> i32 {
+        let (min, max) = faces.clone().into_inner();
+        match &mut self.evaluation {
+            Evaluation::Rand(rng) | Evaluation::Critical(rng) => rng.gen_range(faces),
+            Evaluation::Min => min,
+            Evaluation::Mid => min + (max - min) / 2,
+            Evaluation::Max => max,
+        }
+    }
+
+    /// Rolls a replacement for a die that is rerolled for as long as it meets
+    /// a target. Deterministic evaluations choose directly from the faces that
+    /// do not meet the target, so the reroll is only needed once.
+    fn reroll_die(
+        &mut self,
+        faces: RangeInclusive<i32>,
+        comparison: Comparison,
+        value: i32,
+    ) -> i32 {
+        if self.is_random() {
+            return self.roll_die(faces);
+        }
+
+        let remaining: Vec<i32> = faces
+            .clone()
+            .filter(|&face| !compare(comparison, face, value))
+            .collect();
+
+        match (&self.evaluation, remaining.first(), remaining.last()) {
+            (Evaluation::Min, Some(&min), _) => min,
+            (Evaluation::Mid, Some(_), _) => remaining[(remaining.len() - 1) / 2],
+            (Evaluation::Max, _, Some(&max)) => max,
+            _ => self.roll_die(faces),
+        }
+    }
+
+    fn count(
+        &mut self,
+        roll: &Node,
+        success: &Target,
+        failure: Option<&Target>,
+    ) -> Result<(), Error> {
+        self.visit(&success.value)?;
+        let success = (
+            success.comparison,
+            self.results.pop().ok_or(Error::StackUnderflow)?,
+        );
+
+        let failure = match failure {
+            Some(failure) => {
+                self.visit(&failure.value)?;
+                let value = self.results.pop().ok_or(Error::StackUnderflow)?;
+                Some((failure.comparison, value))
+            }
+            None => None,
+        };
+
+        let start = self.rolls.len();
+        self.visit(roll)?;
+        self.results.pop().ok_or(Error::StackUnderflow)?;
+
+        let mut total = 0;
+        for die in self.rolls[start..].iter_mut().filter(|die| die.keep) {
+            if compare(success.0, die.result, success.1) {
+                die.outcome = Some(Outcome::Success);
+                total += 1;
+            } else if failure
+                .is_some_and(|(comparison, value)| compare(comparison, die.result, value))
+            {
+                die.outcome = Some(Outcome::Failure);
+                total -= 1;
+            }
+        }
+
+        // The roll's total is replaced by the count it contributes.
+        if let Some(pool) = self.pools.last_mut() {
+            pool.total = total;
+        }
+
+        self.results.push(total);
+        Ok(())
+    }
+
+    fn tag(&mut self, label: &str, right: &Node) -> Result<(), Error> {
         self.visit(right)?;
+        let Some(&value) = self.results.last() else {
+            return Err(Error::StackUnderflow);
+        };
+
+        let value = if self.negated {
+            value.checked_neg().ok_or(Error::Overflow)?
+        } else {
+            value
+        };
+        let subtotal = self.breakdown.entry(label.to_string()).or_default();
+        *subtotal = subtotal.checked_add(value).ok_or(Error::Overflow)?;
+        Ok(())
+    }
+
+    fn neg(&mut self, right: &Node) -> Result<(), Error> {
+        self.visit_negated(right)?;
         let Some(right) = self.results.pop() else {
             return Err(Error::StackUnderflow);
         };
-        self.results.push(-right);
+        self.results
+            .push(right.checked_neg().ok_or(Error::Overflow)?);
         Ok(())
     }
 
@@ -298,7 +872,8 @@ impl<TRng: Rng> Evaluator<TRng> {
             return Err(Error::StackUnderflow);
         };
 
-        self.results.push(left + right);
+        self.results
+            .push(left.checked_add(right).ok_or(Error::Overflow)?);
         Ok(())
     }
 
@@ -307,15 +882,24 @@ impl<TRng: Rng> Evaluator<TRng> {
         let Some(left) = self.results.pop() else {
             return Err(Error::StackUnderflow);
         };
-        self.visit(right)?;
+        self.visit_negated(right)?;
         let Some(right) = self.results.pop() else {
             return Err(Error::StackUnderflow);
         };
 
-        self.results.push(left - right);
+        self.results
+            .push(left.checked_sub(right).ok_or(Error::Overflow)?);
         Ok(())
     }
 
+    /// Visits a node whose value is subtracted from the total.
+    fn visit_negated(&mut self, node: &Node) -> Result<(), Error> {
+        self.negated = !self.negated;
+        let result = self.visit(node);
+        self.negated = !self.negated;
+        result
+    }
+
     fn mul(&mut self, left: &Node, right: &Node) -> Result<(), Error> {
         self.visit(left)?;
         let Some(left) = self.results.pop() else {
@@ -326,7 +910,8 @@ impl<TRng: Rng> Evaluator<TRng> {
             return Err(Error::StackUnderflow);
         };
 
-        self.results.push(left * right);
+        self.results
+            .push(left.checked_mul(right).ok_or(Error::Overflow)?);
         Ok(())
     }
 
@@ -344,24 +929,208 @@ impl<TRng: Rng> Evaluator<TRng> {
             return Err(Error::DivideByZero);
         }
 
-        self.results.push(left / right);
+        self.results
+            .push(left.checked_div(right).ok_or(Error::Overflow)?);
         Ok(())
     }
+
+    fn compare(&mut self, comparison: Comparison, left: &Node, right: &Node) -> Result<(), Error> {
+        self.visit(left)?;
+        let Some(left) = self.results.pop() else {
+            return Err(Error::StackUnderflow);
+        };
+        self.visit(right)?;
+        let Some(right) = self.results.pop() else {
+            return Err(Error::StackUnderflow);
+        };
+
+        self.results.push(compare(comparison, left, right) as i32);
+        Ok(())
+    }
+
+    fn cond(&mut self, condition: &Node, then: &Node, otherwise: &Node) -> Result<(), Error> {
+        self.visit(condition)?;
+        let Some(condition) = self.results.pop() else {
+            return Err(Error::StackUnderflow);
+        };
+
+        if condition != 0 {
+            self.branches.push(Branch::Then);
+            self.visit(then)
+        } else {
+            self.branches.push(Branch::Else);
+            self.visit(otherwise)
+        }
+    }
+
+    fn hit(&mut self, attack: &Node, target: &Node, damage: &Node) -> Result<(), Error> {
+        // Only damage is rolled as a critical hit, never the attack itself.
+        let was_critical = self.critical;
+        self.critical = false;
+
+        let start = self.rolls.len();
+        self.visit(attack)?;
+        let Some(attack) = self.results.pop() else {
+            return Err(Error::StackUnderflow);
+        };
+        let natural = self.rolls[start..]
+            .iter()
+            .find(|die| die.keep && die.sides == 20 && !die.fudge)
+            .map(|die| die.result);
+
+        self.visit(target)?;
+        let Some(target) = self.results.pop() else {
+            return Err(Error::StackUnderflow);
+        };
+        self.critical = was_critical;
+
+        let branch = match natural {
+            Some(20) => Branch::Critical,
+            Some(1) => Branch::Miss,
+            _ if attack >= target => Branch::Hit,
+            _ => Branch::Miss,
+        };
+        self.branches.push(branch);
+
+        match branch {
+            Branch::Critical => {
+                self.critical = true;
+                let result = self.visit(damage);
+                self.critical = was_critical;
+                result
+            }
+            Branch::Hit => self.visit(damage),
+            _ => self.lit(0),
+        }
+    }
+
+    fn call(&mut self, function: Function, args: &[Node]) -> Result<(), Error> {
+        // Rounding functions round the quotient of a division argument rather
+        // than rounding the already truncated result.
+        if let (Function::Floor | Function::Ceil | Function::Round, [Node::Div { left, right }]) =
+            (function, args)
+        {
+            self.visit(left)?;
+            let Some(left) = self.results.pop() else {
+                return Err(Error::StackUnderflow);
+            };
+            self.visit(right)?;
+            let Some(right) = self.results.pop() else {
+                return Err(Error::StackUnderflow);
+            };
+
+            if right == 0 {
+                return Err(Error::DivideByZero);
+            }
+
+            let quotient = divide(function, left, right).ok_or(Error::Overflow)?;
+            self.results.push(quotient);
+            return Ok(());
+        }
+
+        let mut values = Vec::with_capacity(args.len());
+        for arg in args {
+            self.visit(arg)?;
+            values.push(self.results.pop().ok_or(Error::StackUnderflow)?);
+        }
+
+        let value = match function {
+            Function::Min => values.into_iter().min(),
+            Function::Max => values.into_iter().max(),
+            Function::Floor | Function::Ceil | Function::Round => values.first().copied(),
+            Function::Abs => match values.first() {
+                Some(value) => Some(value.checked_abs().ok_or(Error::Overflow)?),
+                None => None,
+            },
+        };
+
+        self.results.push(value.ok_or(Error::StackUnderflow)?);
+        Ok(())
+    }
+}
+
+/// Divides `left` by `right`, rounding the quotient as `function` does, or
+/// returns `None` if `right` is zero or the quotient overflows. Functions that
+/// do not round truncate the quotient like `/`.
+pub(super) fn divide(function: Function, left: i32, right: i32) -> Option<i32> {
+    if right == 0 {
+        return None;
+    }
+
+    // Work with a positive divisor (and in 64 bits, so that neither negation
+    // nor doubling can overflow).
+    let (left, right) = if right < 0 {
+        (-(left as i64), -(right as i64))
+    } else {
+        (left as i64, right as i64)
+    };
+
+    let quotient = match function {
+        Function::Floor => left.div_euclid(right),
+        Function::Ceil => -(-left).div_euclid(right),
+        Function::Round => (2 * left + right).div_euclid(2 * right),
+        _ => left / right,
+    };
+
+    i32::try_from(quotient).ok()
+}
+
+/// Returns the faces of a die with the given number of sides, or of a Fudge
+/// die.
+pub(super) fn faces(sides: i32, fudge: bool) -> RangeInclusive<i32> {
+    if fudge {
+        -1..=1
+    } else {
+        1..=sides
+    }
+}
+
+/// Returns whether a die with the given highest face and result should
+/// explode, either by meeting `target` or rolling its maximum.
+pub(super) fn explodes(target: Option<(Comparison, i32)>, max: i32, result: i32) -> bool {
+    match target {
+        Some((comparison, value)) => compare(comparison, result, value),
+        None => result == max,
+    }
+}
+
+/// Applies `comparison` to a die result and a target value.
+pub(super) fn compare(comparison: Comparison, result: i32, value: i32) -> bool {
+    match comparison {
+        Comparison::Equal => result == value,
+        Comparison::Less => result < value,
+        Comparison::LessOrEqual => result <= value,
+        Comparison::Greater => result > value,
+        Comparison::GreaterOrEqual => result >= value,
+    }
+}
+
+impl DieRoll {
+    /// Returns the faces of the die.
+    pub fn faces(&self) -> RangeInclusive<i32> {
+        faces(self.sides, self.fudge)
+    }
 }
 
 impl Display for DieRoll {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let sides = if self.fudge {
+            "F".to_string()
+        } else {
+            self.sides.to_string()
+        };
+
         if self.keep {
             write!(
                 f,
                 "\x1B[32m[d{}:\x1B[22m\x1B[1m{}\x1B[22m]\x1B[39m",
-                self.sides, self.result
+                sides, self.result
             )
         } else {
             write!(
                 f,
                 "\x1B[9m\x1B[31m[d{}:\x1B[22m\x1B[1m{}\x1B[22m]\x1B[39m\x1B[29m",
-                self.sides, self.result
+                sides, self.result
             )
         }
     }
@@ -381,7 +1150,19 @@ impl Display for Error {
                 selection_size, pool_size
             ),
             Error::DivideByZero => write!(f, "Division by zero"),
+            Error::UnboundVariable(name) => write!(f, "Unknown variable '@{name}'"),
+            Error::InvalidDie { sides } => write!(f, "Invalid die: d{sides}"),
             Error::StackUnderflow => write!(f, "Stack underflow"),
+            Error::Overflow => write!(f, "Result is too large"),
+            Error::TooManyDice { max_dice } => {
+                write!(f, "Too many dice (at most {max_dice} may be rolled)")
+            }
+            Error::TooDeep { max_depth } => {
+                write!(
+                    f,
+                    "Expression is too deeply nested (at most {max_depth} levels)"
+                )
+            }
         }
     }
 }
@@ -0,0 +1,438 @@
+// Copyright (c) 2025 Jonathon B. Cobb
+// Licensed under the MIT License
+
+//! This module computes the exact expected value of a dice expression as a
+//! fraction, so that the average of `2d6` is exactly 7 and the average hit
+//! points of `3d6 + 3` are exactly 27/2.
+//!
+//! Expectation is linear, so sums, products (whose operands roll separate, and
+//! therefore independent, dice) and plain rolls are handled directly. A roll
+//! with a single keep, drop, advantage or disadvantage selection is handled by
+//! counting the outcomes of its dice exactly. Anything else, such as division
+//! or exploding dice, is reported as [`Error::Intractable`], in which case the
+//! mean of the [distribution](super::dist) may be used instead.
+
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    ops::RangeInclusive,
+};
+
+use super::ast::{Node, Selection};
+use super::dist::Error;
+use super::eval::{compare, faces};
+
+/// The greatest number of steps spent counting the outcomes of a roll with a
+/// selection. Rolls that would take more are reported as intractable up front,
+/// as with the states of a [distribution](super::dist).
+const MAX_STEPS: i128 = 10_000_000;
+
+/// An exact fraction, always kept in lowest terms with a positive
+/// denominator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ratio {
+    numer: i128,
+    denom: i128,
+}
+
+impl Ratio {
+    /// Creates the fraction `numer / denom`, or returns `None` if `denom` is
+    /// zero.
+    pub fn new(numer: i128, denom: i128) -> Option<Self> {
+        if denom == 0 {
+            return None;
+        }
+
+        let divisor = gcd(numer, denom) * denom.signum();
+        Some(Self {
+            numer: numer / divisor,
+            denom: denom / divisor,
+        })
+    }
+
+    /// Creates a whole number.
+    pub fn integer(value: i128) -> Self {
+        Self {
+            numer: value,
+            denom: 1,
+        }
+    }
+
+    pub fn numer(self) -> i128 {
+        self.numer
+    }
+
+    pub fn denom(self) -> i128 {
+        self.denom
+    }
+
+    /// Returns the greatest integer not greater than the fraction.
+    pub fn floor(self) -> i128 {
+        self.numer.div_euclid(self.denom)
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.numer as f64 / self.denom as f64
+    }
+
+    fn checked_add(self, other: Self) -> Option<Self> {
+        let numer = self
+            .numer
+            .checked_mul(other.denom)?
+            .checked_add(other.numer.checked_mul(self.denom)?)?;
+        Self::new(numer, self.denom.checked_mul(other.denom)?)
+    }
+
+    fn checked_mul(self, other: Self) -> Option<Self> {
+        Self::new(
+            self.numer.checked_mul(other.numer)?,
+            self.denom.checked_mul(other.denom)?,
+        )
+    }
+
+    fn checked_neg(self) -> Option<Self> {
+        Some(Self {
+            numer: self.numer.checked_neg()?,
+            denom: self.denom,
+        })
+    }
+}
+
+impl Display for Ratio {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        if self.denom == 1 {
+            write!(f, "{}", self.numer)
+        } else {
+            write!(f, "{}/{}", self.numer, self.denom)
+        }
+    }
+}
+
+/// Computes the exact expected value of the expression rooted at `node`, which
+/// should be [normalized](super::simplify::normalize) so that constant counts
+/// and sides are literals.
+pub fn expected_value(node: &Node) -> Result<Ratio, Error> {
+    match node {
+        Node::Lit { value } => Ok(Ratio::integer(*value as i128)),
+        Node::Tag { right, .. } => expected_value(right),
+        Node::Neg { right } => expected_value(right)?
+            .checked_neg()
+            .ok_or(Error::Intractable),
+        Node::Add { left, right } => expected_value(left)?
+            .checked_add(expected_value(right)?)
+            .ok_or(Error::Intractable),
+        Node::Sub { left, right } => expected_value(left)?
+            .checked_add(
+                expected_value(right)?
+                    .checked_neg()
+                    .ok_or(Error::Intractable)?,
+            )
+            .ok_or(Error::Intractable),
+        Node::Mul { left, right } => expected_value(left)?
+            .checked_mul(expected_value(right)?)
+            .ok_or(Error::Intractable),
+        Node::Roll {
+            count,
+            sides,
+            fudge,
+            select,
+        } => roll(count, sides, *fudge, select.as_deref()),
+        Node::Count {
+            roll,
+            success,
+            failure,
+        } => {
+            let Node::Roll {
+                count,
+                sides,
+                fudge,
+                select: None,
+            } = roll.as_ref()
+            else {
+                return Err(Error::Intractable);
+            };
+            let (count, faces) = pool(count, sides, *fudge)?;
+            let success = (success.comparison, literal(&success.value)?);
+            let failure = match failure {
+                Some(failure) => Some((failure.comparison, literal(&failure.value)?)),
+                None => None,
+            };
+
+            // Each die adds one if it succeeds and subtracts one if it fails.
+            let mut numer = 0i128;
+            for face in faces.clone() {
+                if compare(success.0, face, success.1) {
+                    numer += 1;
+                } else if failure
+                    .is_some_and(|(comparison, value)| compare(comparison, face, value))
+                {
+                    numer -= 1;
+                }
+            }
+
+            let per_die = Ratio::new(numer, faces.count() as i128).ok_or(Error::Intractable)?;
+            per_die
+                .checked_mul(Ratio::integer(count))
+                .ok_or(Error::Intractable)
+        }
+        _ => Err(Error::Intractable),
+    }
+}
+
+fn roll(count: &Node, sides: &Node, fudge: bool, select: Option<&Node>) -> Result<Ratio, Error> {
+    let (count, faces) = pool(count, sides, fudge)?;
+
+    let Some(select) = select else {
+        let (min, max) = faces.into_inner();
+        return Ratio::new(count * (min as i128 + max as i128), 2).ok_or(Error::Intractable);
+    };
+
+    let Node::Select {
+        selection,
+        next: None,
+    } = select
+    else {
+        return Err(Error::Intractable);
+    };
+
+    let total = || {
+        roll(
+            &Node::Lit {
+                value: count as i32,
+            },
+            sides,
+            fudge,
+            None,
+        )
+    };
+    let kept = |selection_size: &Option<Box<Node>>, highest: bool| -> Result<i128, Error> {
+        let size = match selection_size.as_deref() {
+            Some(Node::Lit { value }) => *value as i128,
+            None => 1,
+            Some(_) => return Err(Error::Intractable),
+        };
+        if size < 0 || size > count {
+            return Err(Error::InvalidSelection {
+                selection_size: size.max(0) as usize,
+                pool_size: count as usize,
+            });
+        }
+        Ok(if highest { size } else { count - size })
+    };
+
+    match selection {
+        Selection::KeepHighest { count: size } => highest(count, faces, kept(size, true)?),
+        Selection::DropLowest { count: size } => highest(count, faces, kept(size, false)?),
+        Selection::KeepLowest { count: size } => {
+            let dropped = highest(count, faces, kept(size, false)?)?;
+            subtract(total()?, dropped)
+        }
+        Selection::DropHighest { count: size } => {
+            let dropped = highest(count, faces, kept(size, true)?)?;
+            subtract(total()?, dropped)
+        }
+        Selection::Advantage => advantage(count, faces),
+        Selection::Disadvantage => {
+            // The lesser of two rolls is their sum less the greater.
+            let both = total()?
+                .checked_mul(Ratio::integer(2))
+                .ok_or(Error::Intractable)?;
+            subtract(both, advantage(count, faces)?)
+        }
+        _ => Err(Error::Intractable),
+    }
+}
+
+fn literal(node: &Node) -> Result<i32, Error> {
+    match node {
+        Node::Lit { value } => Ok(*value),
+        _ => Err(Error::Intractable),
+    }
+}
+
+/// Returns the number of dice and their faces for a roll of constant dice.
+fn pool(count: &Node, sides: &Node, fudge: bool) -> Result<(i128, RangeInclusive<i32>), Error> {
+    let (Node::Lit { value: count }, Node::Lit { value: sides }) = (count, sides) else {
+        return Err(Error::Intractable);
+    };
+
+    if *sides < 1 {
+        return Err(Error::InvalidDie { sides: *sides });
+    }
+
+    Ok(((*count).max(0) as i128, faces(*sides, fudge)))
+}
+
+/// Computes the expected total of the highest `kept` of `count` dice. The
+/// `j`-th highest die is at least `x` exactly when at least `j` dice are, so its
+/// expectation is its least face plus the sum of those probabilities over the
+/// faces above.
+fn highest(count: i128, faces: RangeInclusive<i32>, kept: i128) -> Result<Ratio, Error> {
+    let (min, max) = faces.into_inner();
+    let sides = (max - min + 1) as i128;
+    check_steps(
+        (sides - 1)
+            .checked_mul(kept)
+            .and_then(|steps| steps.checked_mul(count)),
+    )?;
+    let denom = checked_pow(sides, count)?;
+
+    let mut numer = 0i128;
+    for x in (min + 1)..=max {
+        let above = (max - x + 1) as i128;
+        let below = (x - min) as i128;
+
+        for j in 1..=kept {
+            // The number of outcomes in which at least `j` dice are at least `x`.
+            for i in j..=count {
+                let ways = binomial(count, i)?
+                    .checked_mul(checked_pow(above, i)?)
+                    .and_then(|ways| ways.checked_mul(checked_pow(below, count - i).ok()?))
+                    .ok_or(Error::Intractable)?;
+                numer = numer.checked_add(ways).ok_or(Error::Intractable)?;
+            }
+        }
+    }
+
+    let least = Ratio::integer(kept * min as i128);
+    Ratio::new(numer, denom)
+        .and_then(|above_least| above_least.checked_add(least))
+        .ok_or(Error::Intractable)
+}
+
+/// Computes the expected greater total of two rolls of `count` dice, from the
+/// number of ways to roll each total.
+fn advantage(count: i128, faces: RangeInclusive<i32>) -> Result<Ratio, Error> {
+    let (min, max) = faces.into_inner();
+    if min == max {
+        return Ok(Ratio::integer(count * min as i128));
+    }
+
+    // Each die adds its faces to each total the dice before it can make.
+    let sides = (max - min + 1) as i128;
+    let totals = count.checked_mul(sides - 1).map(|range| range + 1);
+    check_steps(totals.and_then(|totals| totals.checked_mul(sides)?.checked_mul(count)))?;
+
+    let mut ways = vec![1i128];
+    for _ in 0..count {
+        let mut next = vec![0i128; ways.len() + (max - min) as usize];
+        for (total, &w) in ways.iter().enumerate() {
+            for face in 0..=(max - min) as usize {
+                next[total + face] = next[total + face]
+                    .checked_add(w)
+                    .ok_or(Error::Intractable)?;
+            }
+        }
+        ways = next;
+    }
+
+    // The greater total is at most `v` in `C(v)²` of the outcomes, where
+    // `C(v)` is the number of ways to roll at most `v`.
+    let mut numer = 0i128;
+    let mut below = 0i128;
+    let mut cumulative = 0i128;
+    for (offset, w) in ways.iter().enumerate() {
+        cumulative = cumulative.checked_add(*w).ok_or(Error::Intractable)?;
+        let at_most = cumulative
+            .checked_mul(cumulative)
+            .ok_or(Error::Intractable)?;
+        let total = offset as i128 + count * min as i128;
+        numer = (at_most - below)
+            .checked_mul(total)
+            .and_then(|term| numer.checked_add(term))
+            .ok_or(Error::Intractable)?;
+        below = at_most;
+    }
+
+    Ratio::new(numer, below).ok_or(Error::Intractable)
+}
+
+/// Fails if counting outcomes would take more than [`MAX_STEPS`] steps, or a
+/// number of steps too large to compute.
+fn check_steps(steps: Option<i128>) -> Result<(), Error> {
+    match steps {
+        Some(steps) if steps <= MAX_STEPS => Ok(()),
+        _ => Err(Error::Intractable),
+    }
+}
+
+fn subtract(left: Ratio, right: Ratio) -> Result<Ratio, Error> {
+    right
+        .checked_neg()
+        .and_then(|right| left.checked_add(right))
+        .ok_or(Error::Intractable)
+}
+
+fn checked_pow(base: i128, exp: i128) -> Result<i128, Error> {
+    u32::try_from(exp)
+        .ok()
+        .and_then(|exp| base.checked_pow(exp))
+        .ok_or(Error::Intractable)
+}
+
+fn binomial(n: i128, k: i128) -> Result<i128, Error> {
+    let mut result = 1i128;
+    for i in 0..k.min(n - k) {
+        result = result.checked_mul(n - i).ok_or(Error::Intractable)? / (i + 1);
+    }
+    Ok(result)
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dice::{dist, parser, simplify};
+
+    fn expected(input: &str) -> Result<Ratio, Error> {
+        expected_value(&simplify::normalize(&parser::parse(input).unwrap()))
+    }
+
+    #[test]
+    fn test_expected_value() {
+        let exact = |input: &str| expected(input).unwrap().to_string();
+
+        assert_eq!(exact("2d6"), "7");
+        assert_eq!(exact("3d6 + 3"), "27/2");
+        assert_eq!(exact("d20adv"), "553/40");
+        assert_eq!(exact("2d20kh1"), "553/40");
+        assert_eq!(exact("d20dis"), "287/40");
+        assert_eq!(exact("4d6kh3"), "15869/1296");
+        assert_eq!(exact("4d6d1"), "15869/1296");
+        assert_eq!(exact("4dF"), "0");
+
+        for input in [
+            "4d6kl3",
+            "5d8dh2",
+            "2d6adv",
+            "3d4dis + 1",
+            "8d6>=5f1",
+            "2 * (d4 + 1) - d6",
+        ] {
+            let mean: f64 = dist::distribution(&parser::parse(input).unwrap())
+                .unwrap()
+                .into_iter()
+                .map(|(value, p)| value as f64 * p)
+                .sum();
+            let exact = expected(input).unwrap().to_f64();
+            assert!((mean - exact).abs() < 1e-9, "{input}: {mean} != {exact}");
+        }
+
+        assert!(matches!(expected("d6!"), Err(Error::Intractable)));
+        assert!(expected("4d6kh5").is_err());
+
+        // Selections over too many outcomes to count are given up on up front.
+        for input in ["100d1000adv", "50d100000dis", "4d10000000kh1"] {
+            assert!(
+                matches!(expected(input), Err(Error::Intractable)),
+                "{input}"
+            );
+        }
+    }
+}
@@ -10,12 +10,25 @@
 //! - Contiguous sequences of decimal digits as tokenized as integers.
 //! - Contiguous sequences of alphabetic characters are tokenized as words. The
 //!   following words are recognized as valid: `d`, `k`, `kh`, `kl`, `dh`, `dl`,
-//!  `adv`, `dis`, `da`, `ad`.
-//! - Words not listed above must not appear in the expression.
+//!   `adv`, `dis`, `da`, `ad`, `r`, `ro`, `cs`, `f`, `dF`, `x`, `hit`, `min`,
+//!   `max`, `floor`, `ceil`, `round`, `abs`.
+//! - The word `dF` always ends after the `F`, so that selections may follow it
+//!   directly (e.g. `4dFkh2`).
+//! - Words not listed above must not appear in the expression. An invalid word
+//!   is reported with a suggestion of the most similar valid word.
+//! - A `[` followed by a label made of letters, spaces, hyphens, and
+//!   underscores and then a `]` is tokenized as a label (e.g. `[fire]`), unless
+//!   the label is one of the words above. Otherwise, `[` and `]` are symbols.
+//! - The symbol `@` followed by a contiguous sequence of alphanumeric
+//!   characters and underscores is tokenized as a variable (e.g. `@dex`).
 //! - The following symbols are recognized as distinct tokens: `+`, `-`, `*`,
-//!   `/`, `%`, `(`, `)`, `[`, `]`. The symbols `×` and `÷` are also recognized
-//!   as equivalent to `*` and `/`, respectively.
+//!   `/`, `%`, `(`, `)`, `[`, `]`, `{`, `}`, `,`, `?`, `:`, `!`, `=`, `<`,
+//!   `<=`, `>`, `>=`. The symbols `×` and `÷` are also recognized as
+//!   equivalent to `*` and `/`, respectively.
 //! - No other characters may appear in the expression.
+//!
+//! Every token and error is located by its [`Span`], the range of byte offsets
+//! it occupies in the input.
 
 use std::{
     error::Error as StdError,
@@ -24,9 +37,89 @@ use std::{
     str::CharIndices,
 };
 
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
 /// The set of valid words that can appear in a dice expression.
-const VALID_WORDS: &'static [&'static str] =
-    &["d", "k", "kh", "kl", "dh", "dl", "adv", "dis", "da", "ad"];
+const VALID_WORDS: &'static [&'static str] = &[
+    "d", "k", "kh", "kl", "dh", "dl", "adv", "dis", "da", "ad", "r", "ro", "cs", "f", "dF", "x",
+    "hit", "min", "max", "floor", "ceil", "round", "abs",
+];
+
+/// The range of byte offsets occupied by a token or error in the input. Spans
+/// sent to the frontend are first converted to UTF-16 code units with
+/// [`Span::to_utf16`], since JavaScript indexes strings in those.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Span {
+    /// The offset of the first byte.
+    pub start: usize,
+
+    /// The offset one past the last byte.
+    pub end: usize,
+}
+
+impl Span {
+    /// Converts a span of byte offsets in `text` to a span of offsets in UTF-16
+    /// code units.
+    pub fn to_utf16(self, text: &str) -> Self {
+        let offset = |byte: usize| -> usize {
+            text.char_indices()
+                .take_while(|&(i, _)| i < byte)
+                .map(|(_, c)| c.len_utf16())
+                .sum()
+        };
+
+        Span {
+            start: offset(self.start),
+            end: offset(self.end),
+        }
+    }
+}
+
+/// Broad categories of tokens, used to highlight the syntax of an expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[ts(export, rename = "DiceTokenKind")]
+#[serde(rename_all = "camelCase")]
+pub enum TokenKind {
+    /// An integer literal.
+    Integer,
+
+    /// A word describing dice, such as `d` or `kh`.
+    Word,
+
+    /// The name of a function, such as `max` or `hit`.
+    Function,
+
+    /// A variable, such as `@dex`.
+    Variable,
+
+    /// A label, such as `[fire]`.
+    Label,
+
+    /// An arithmetic operator.
+    Operator,
+
+    /// A comparison operator.
+    Comparison,
+
+    /// Any other symbol, such as a parenthesis.
+    Punctuation,
+
+    /// Input that is not a valid token.
+    Error,
+}
+
+/// A token's kind and location, used to highlight the syntax of an expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, rename = "DiceToken")]
+pub struct SpannedKind {
+    /// The kind of token.
+    pub kind: TokenKind,
+
+    /// The location of the token in the input.
+    pub span: Span,
+}
 
 /// The types of tokens that can be produced by the lexer.
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -37,6 +130,12 @@ pub enum Token<'a> {
     /// A recognized word.
     Word(&'a str),
 
+    /// A variable name, without the leading `@`.
+    Variable(&'a str),
+
+    /// A label enclosed in `[` and `]`, without the brackets.
+    Label(&'a str),
+
     /// The symbol `+`.
     Plus,
 
@@ -52,11 +151,38 @@ pub enum Token<'a> {
     /// The symbol `%`.
     Percent,
 
-    /// The symbol `(` or `[`.
+    /// The symbol `(`, `[`, or `{`.
     Open(char),
 
-    /// The symbol `)` or `]`.
+    /// The symbol `)`, `]`, or `}`.
     Close(char),
+
+    /// The symbol `,`.
+    Comma,
+
+    /// The symbol `?`.
+    Question,
+
+    /// The symbol `:`.
+    Colon,
+
+    /// The symbol `!`.
+    Bang,
+
+    /// The symbol `=`.
+    Equal,
+
+    /// The symbol `<`.
+    Less,
+
+    /// The symbol `<=`.
+    LessEqual,
+
+    /// The symbol `>`.
+    Greater,
+
+    /// The symbol `>=`.
+    GreaterEqual,
 }
 
 /// A lexical analyzer for dice expressions. The lexer implements an `Iterator`
@@ -65,11 +191,24 @@ pub struct Lexer<'a> {
     input: &'a str,
     chars: CharIndices<'a>,
     current: Option<(usize, char)>,
+
+    /// The location of the most recent token.
+    span: Span,
+}
+
+/// A lexical error and its location in the input.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Error {
+    /// What went wrong.
+    pub kind: ErrorKind,
+
+    /// The location of the offending input.
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 /// Lexical errors that can occur during tokenization.
-pub enum Error {
+pub enum ErrorKind {
     /// An invalid character was encountered.
     InvalidCharacter(char),
 
@@ -78,6 +217,55 @@ pub enum Error {
     ParseIntError(ParseIntError),
 }
 
+/// Tokenizes `input` for syntax highlighting, including any invalid input.
+pub fn highlight(input: &str) -> Vec<SpannedKind> {
+    let mut lexer = Lexer::new(input);
+    let mut tokens = Vec::new();
+
+    while let Some(token) = lexer.next() {
+        let kind = match token {
+            Ok(token) => token.kind(),
+            Err(_) => TokenKind::Error,
+        };
+        tokens.push(SpannedKind {
+            kind,
+            span: lexer.span(),
+        });
+    }
+
+    tokens
+}
+
+/// Returns the valid word most similar to `word`, if any is similar enough to
+/// be a likely typo.
+fn suggest(word: &str) -> Option<&'static str> {
+    VALID_WORDS
+        .iter()
+        .map(|&valid| (distance(word, valid), !valid.contains(word), valid))
+        .filter(|&(distance, _, _)| distance <= 2 && distance < word.chars().count())
+        .min_by_key(|&(distance, unrelated, _)| (distance, unrelated))
+        .map(|(_, _, valid)| valid)
+}
+
+/// Computes the Levenshtein distance between two words.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + (ca != cb) as usize;
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
 impl<'a> Lexer<'a> {
     pub fn new(input: &'a str) -> Lexer<'a> {
         let chars = input.char_indices();
@@ -86,9 +274,16 @@ impl<'a> Lexer<'a> {
             input,
             chars,
             current: None,
+            span: Span::default(),
         }
     }
 
+    /// Returns the location of the most recent token, or an empty span at the
+    /// end of the input once every token has been produced.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
     fn peek(&self) -> Option<char> {
         self.current.map(|(_, c)| c)
     }
@@ -116,6 +311,25 @@ impl<'a> Iterator for Lexer<'a> {
             self.next_char();
         }
 
+        let start = self.peek_position();
+        let token = self.token();
+        self.span = Span {
+            start,
+            end: self.peek_position(),
+        };
+
+        token.map(|token| {
+            token.map_err(|kind| Error {
+                kind,
+                span: self.span,
+            })
+        })
+    }
+}
+
+impl<'a> Lexer<'a> {
+    /// Consumes the next token, which starts at the current character.
+    fn token(&mut self) -> Option<Result<Token<'a>, ErrorKind>> {
         let Some(ch) = self.peek() else {
             // End of input
             return None;
@@ -130,7 +344,7 @@ impl<'a> Iterator for Lexer<'a> {
             let j = self.peek_position();
             let n = match self.input[i..j].parse() {
                 Ok(n) => n,
-                Err(err) => return Some(Err(Error::ParseIntError(err))),
+                Err(err) => return Some(Err(ErrorKind::ParseIntError(err))),
             };
             return Some(Ok(Token::Integer(n)));
         }
@@ -139,18 +353,62 @@ impl<'a> Iterator for Lexer<'a> {
             // Consume a word (take all contiguous alphabetic characters)
             let i = self.peek_position();
 
-            while self.next_char().map_or(false, |c| c.is_alphabetic()) {}
+            while self.next_char().map_or(false, |c| c.is_alphabetic()) {
+                if &self.input[i..self.peek_position()] == "dF" {
+                    break;
+                }
+            }
 
             let j = self.peek_position();
             let word = &self.input[i..j];
 
             if !VALID_WORDS.contains(&word) {
-                return Some(Err(Error::InvalidWord(word.to_string())));
+                return Some(Err(ErrorKind::InvalidWord(word.to_string())));
             }
 
             return Some(Ok(Token::Word(word)));
         }
 
+        if ch == '[' {
+            // Consume a label if the brackets enclose one
+            let i = self.peek_position() + ch.len_utf8();
+
+            if let Some(len) = self.input[i..].find(']') {
+                let label = self.input[i..i + len].trim();
+
+                if label.starts_with(char::is_alphabetic)
+                    && label
+                        .chars()
+                        .all(|c| c.is_alphabetic() || c == ' ' || c == '-' || c == '_')
+                    && !VALID_WORDS.contains(&label)
+                {
+                    while self.peek_position() <= i + len {
+                        self.next_char();
+                    }
+
+                    return Some(Ok(Token::Label(label)));
+                }
+            }
+        }
+
+        if ch == '@' {
+            // Consume a variable (take all contiguous alphanumeric characters
+            // and underscores following the '@')
+            let i = self.peek_position() + ch.len_utf8();
+
+            while self
+                .next_char()
+                .is_some_and(|c| c.is_alphanumeric() || c == '_')
+            {}
+
+            let j = self.peek_position();
+            if i == j {
+                return Some(Err(ErrorKind::InvalidCharacter(ch)));
+            }
+
+            return Some(Ok(Token::Variable(&self.input[i..j])));
+        }
+
         // Otherwise, consume a single-character symbol
         self.next_char();
         match ch {
@@ -161,9 +419,26 @@ impl<'a> Iterator for Lexer<'a> {
             '%' => Some(Ok(Token::Percent)),
             '(' => Some(Ok(Token::Open('('))),
             '[' => Some(Ok(Token::Open('['))),
+            '{' => Some(Ok(Token::Open('{'))),
             ')' => Some(Ok(Token::Close(')'))),
             ']' => Some(Ok(Token::Close(']'))),
-            _ => Some(Err(Error::InvalidCharacter(ch))),
+            '}' => Some(Ok(Token::Close('}'))),
+            ',' => Some(Ok(Token::Comma)),
+            '?' => Some(Ok(Token::Question)),
+            ':' => Some(Ok(Token::Colon)),
+            '!' => Some(Ok(Token::Bang)),
+            '=' => Some(Ok(Token::Equal)),
+            '<' if self.peek() == Some('=') => {
+                self.next_char();
+                Some(Ok(Token::LessEqual))
+            }
+            '<' => Some(Ok(Token::Less)),
+            '>' if self.peek() == Some('=') => {
+                self.next_char();
+                Some(Ok(Token::GreaterEqual))
+            }
+            '>' => Some(Ok(Token::Greater)),
+            _ => Some(Err(ErrorKind::InvalidCharacter(ch))),
         }
     }
 }
@@ -173,6 +448,8 @@ impl<'a> Display for Token<'a> {
         match self {
             Token::Integer(i) => write!(f, "{}", i),
             Token::Word(word) => write!(f, "{}", word),
+            Token::Variable(name) => write!(f, "@{}", name),
+            Token::Label(label) => write!(f, "[{}]", label),
             Token::Plus => write!(f, "+"),
             Token::Minus => write!(f, "-"),
             Token::Times => write!(f, "×"),
@@ -180,6 +457,54 @@ impl<'a> Display for Token<'a> {
             Token::Percent => write!(f, "%"),
             Token::Open(ch) => write!(f, "{ch}"),
             Token::Close(ch) => write!(f, "{ch}"),
+            Token::Comma => write!(f, ","),
+            Token::Question => write!(f, "?"),
+            Token::Colon => write!(f, ":"),
+            Token::Bang => write!(f, "!"),
+            Token::Equal => write!(f, "="),
+            Token::Less => write!(f, "<"),
+            Token::LessEqual => write!(f, "<="),
+            Token::Greater => write!(f, ">"),
+            Token::GreaterEqual => write!(f, ">="),
+        }
+    }
+}
+
+impl<'a> Token<'a> {
+    /// Returns the broad category of the token.
+    pub fn kind(&self) -> TokenKind {
+        match self {
+            Token::Integer(_) => TokenKind::Integer,
+            Token::Word("hit" | "min" | "max" | "floor" | "ceil" | "round" | "abs") => {
+                TokenKind::Function
+            }
+            Token::Word(_) => TokenKind::Word,
+            Token::Variable(_) => TokenKind::Variable,
+            Token::Label(_) => TokenKind::Label,
+            Token::Plus | Token::Minus | Token::Times | Token::Divide => TokenKind::Operator,
+            Token::Equal
+            | Token::Less
+            | Token::LessEqual
+            | Token::Greater
+            | Token::GreaterEqual => TokenKind::Comparison,
+            Token::Percent
+            | Token::Open(_)
+            | Token::Close(_)
+            | Token::Comma
+            | Token::Question
+            | Token::Colon
+            | Token::Bang => TokenKind::Punctuation,
+        }
+    }
+}
+
+impl Error {
+    /// Returns a valid word that may have been intended in place of an invalid
+    /// one, e.g. `kh` in place of `khh`.
+    pub fn suggestion(&self) -> Option<&'static str> {
+        match &self.kind {
+            ErrorKind::InvalidWord(word) => suggest(word),
+            _ => None,
         }
     }
 }
@@ -188,10 +513,10 @@ impl StdError for Error {}
 
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        match self {
-            Error::InvalidCharacter(ch) => write!(f, "Invalid character '{}'", ch),
-            Error::InvalidWord(word) => write!(f, "Invalid word '{}'", word),
-            Error::ParseIntError(err) => write!(f, "{}", err),
+        match &self.kind {
+            ErrorKind::InvalidCharacter(ch) => write!(f, "Invalid character '{}'", ch),
+            ErrorKind::InvalidWord(word) => write!(f, "Invalid word '{}'", word),
+            ErrorKind::ParseIntError(err) => write!(f, "{}", err),
         }
     }
 }
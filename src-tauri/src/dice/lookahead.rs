@@ -15,6 +15,12 @@ impl<TIter: Iterator<Item: Clone>> Lookahead<TIter> {
         lookahead
     }
 
+    /// Returns the underlying iterator, which has already produced the peeked
+    /// item.
+    pub fn inner(&self) -> &TIter {
+        &self.iter
+    }
+
     pub fn peek(&self) -> Option<&TIter::Item> {
         self.peek.as_ref()
     }
@@ -0,0 +1,205 @@
+// Copyright (c) 2025 Jonathon B. Cobb
+// Licensed under the MIT License
+
+//! This module finds inline rolls in free-form notes, such as the notes of a
+//! monster's stat block. An inline roll is a dice expression in double square
+//! brackets, optionally followed by a label after a vertical bar, e.g.
+//! `[[2d6 + 3]]` or `[[d20 + 5|Bite]]`. Labels inside the expression, as in
+//! `[[1d8[slashing] + 3]]`, are allowed. Markup that is not closed on the same
+//! line is left as text.
+
+use serde::Serialize;
+use ts_rs::TS;
+
+use super::lexer::Span;
+use super::{parser, DiceBatch, Result};
+
+/// Free-form notes and the inline rolls found in them.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "camelCase")]
+pub struct Notes {
+    /// The text of the notes, including the markup of each inline roll.
+    pub text: String,
+
+    /// The inline rolls in the notes, in order.
+    pub rolls: Vec<InlineRoll>,
+}
+
+/// A dice expression embedded in notes, such as `[[d20 + 5|Bite]]`.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "camelCase")]
+pub struct InlineRoll {
+    /// The location of the markup in the notes, including the brackets.
+    pub span: Span,
+
+    /// The dice expression, without surrounding whitespace.
+    pub expression: String,
+
+    /// The label following the expression, if any.
+    pub label: Option<String>,
+
+    /// The syntax error in the expression, if it is invalid, located within
+    /// the notes rather than the expression.
+    pub error: Option<parser::Error>,
+}
+
+impl Notes {
+    /// Finds the inline rolls in `text`, checking the syntax of each.
+    pub fn parse(text: &str) -> Self {
+        let mut rolls = Vec::new();
+        let mut pos = 0;
+
+        while let Some(offset) = text[pos..].find("[[") {
+            let start = pos + offset;
+            let inner = start + 2;
+
+            match closing(&text[inner..]) {
+                Some(len) => {
+                    rolls.push(InlineRoll::new(text, inner, inner + len));
+                    pos = inner + len + 2;
+                }
+                None => pos = inner,
+            }
+        }
+
+        Notes {
+            text: text.to_string(),
+            rolls,
+        }
+    }
+
+    /// Converts the locations of the inline rolls and their errors to UTF-16
+    /// code units, for the frontend.
+    pub fn into_utf16(mut self) -> Self {
+        for roll in &mut self.rolls {
+            roll.span = roll.span.to_utf16(&self.text);
+            if let Some(error) = &mut roll.error {
+                error.span = error.span.to_utf16(&self.text);
+            }
+        }
+        self
+    }
+}
+
+impl InlineRoll {
+    /// Creates the inline roll whose contents occupy `start..end` of `text`.
+    fn new(text: &str, start: usize, end: usize) -> Self {
+        let contents = &text[start..end];
+        let (expression, label) = match contents.split_once('|') {
+            Some((expression, label)) => (expression, Some(label.trim())),
+            None => (contents, None),
+        };
+
+        let offset = start + (expression.len() - expression.trim_start().len());
+        let expression = expression.trim();
+        let error = parser::parse_batch(expression).err().map(|mut error| {
+            error.span = Span {
+                start: error.span.start + offset,
+                end: error.span.end + offset,
+            };
+            error
+        });
+
+        InlineRoll {
+            span: Span {
+                start: start - 2,
+                end: end + 2,
+            },
+            expression: expression.to_string(),
+            label: label.filter(|label| !label.is_empty()).map(str::to_string),
+            error,
+        }
+    }
+
+    /// Parses the roll's expression, which may be a batch such as
+    /// `[[2x(d20 + 5)]]`, so that it can be rolled.
+    pub fn batch(&self) -> Result<DiceBatch> {
+        DiceBatch::parse(&self.expression)
+    }
+}
+
+/// Returns the length of the contents of an inline roll that begin `text`, up
+/// to the closing `]]`, skipping over any bracketed labels within it.
+fn closing(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut depth = 0;
+
+    for (i, byte) in bytes.iter().enumerate() {
+        match byte {
+            b'\n' => return None,
+            b'[' => depth += 1,
+            b']' if depth > 0 => depth -= 1,
+            b']' if bytes.get(i + 1) == Some(&b']') => return Some(i),
+            b']' => return None,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn test_inline_rolls() {
+        let text = "Bite. [[d20 + 5|Bite]] to hit, [[ 2d6+3 ]] piercing.\n\
+                    Claw [[1d8[slashing] + 3]], [[2d6 +]] and [[d6\n]]\n\
+                    Multiattack [[2x(d20 + 5)|Claws]]";
+        let notes = Notes::parse(text);
+        let rolls: Vec<_> = notes
+            .rolls
+            .iter()
+            .map(|roll| {
+                (
+                    &text[roll.span.start..roll.span.end],
+                    roll.expression.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            rolls,
+            [
+                ("[[d20 + 5|Bite]]", "d20 + 5"),
+                ("[[ 2d6+3 ]]", "2d6+3"),
+                ("[[1d8[slashing] + 3]]", "1d8[slashing] + 3"),
+                ("[[2d6 +]]", "2d6 +"),
+                ("[[2x(d20 + 5)|Claws]]", "2x(d20 + 5)"),
+            ]
+        );
+
+        assert_eq!(notes.rolls[0].label.as_deref(), Some("Bite"));
+        assert_eq!(notes.rolls[1].label, None);
+        assert!(notes.rolls[..3].iter().all(|roll| roll.error.is_none()));
+
+        let error = notes.rolls[3].error.as_ref().unwrap();
+        assert_eq!(error.kind, parser::ErrorKind::UnexpectedEnd);
+        let end = notes.rolls[3].span.end - 2;
+        assert_eq!((error.span.start, error.span.end), (end, end));
+
+        let rolls = notes.rolls[1]
+            .batch()
+            .unwrap()
+            .roll(&mut StdRng::seed_from_u64(0x5eed))
+            .unwrap();
+        assert!((5..=15).contains(&rolls[0].value));
+
+        assert!(notes.rolls[4].error.is_none());
+        assert_eq!(notes.rolls[4].batch().unwrap().exprs().len(), 2);
+
+        // The frontend indexes the notes in UTF-16 code units.
+        let text = "Café — ½ [[d20|Über]]";
+        let notes = Notes::parse(text).into_utf16();
+        let utf16: Vec<u16> = text.encode_utf16().collect();
+        let span = notes.rolls[0].span;
+        assert_eq!(
+            String::from_utf16(&utf16[span.start..span.end]).unwrap(),
+            "[[d20|Über]]"
+        );
+    }
+}
@@ -4,64 +4,435 @@
 //! A recursive descent parser for dice expressions. See [`super`] for a formal
 //! grammar of the language.
 
-use std::{error::Error as StdError, fmt::Display, fmt::Formatter, fmt::Result as FmtResult};
+use std::{
+    error::Error as StdError,
+    fmt::Display,
+    fmt::Formatter,
+    fmt::Result as FmtResult,
+    ops::{Deref, DerefMut},
+};
 
-use super::ast::{Node, Selection};
-use super::lexer::{Error as LexError, Lexer, Token};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use super::ast::{Comparison, Function, Node, Selection, Target};
+use super::eval::{compare, faces};
+use super::lexer::{Error as LexError, ErrorKind as LexErrorKind, Lexer, Span, Token};
 use super::lookahead::Lookahead;
+use super::pp::PP;
+
+/// The tokens being parsed, along with how deeply the production being parsed
+/// is nested.
+struct LookaheadLexer<'a> {
+    tokens: Lookahead<Lexer<'a>>,
+    depth: usize,
+}
+
+/// The tokens that may begin a factor.
+const EXPECTED_FACTOR: &[&str] = &[
+    "integer", "variable", "d", "dF", "(", "-", "hit", "min", "max", "floor", "ceil", "round",
+    "abs",
+];
+
+/// The tokens that may continue an expression.
+const EXPECTED_OPERATOR: &[&str] = &["+", "-", "*", "/", "?", "end of input"];
+
+/// The tokens that may begin a target.
+const EXPECTED_TARGET: &[&str] = &["integer", "=", "<", "<=", ">", ">="];
+
+/// A parsing error, with its location in the input so that it can be pointed
+/// out to the user.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, rename = "DiceSyntaxError")]
+#[serde(rename_all = "camelCase")]
+pub struct Error {
+    /// The kind of error.
+    pub kind: ErrorKind,
+
+    /// A description of the error.
+    pub message: String,
 
-type LookaheadLexer<'a> = Lookahead<Lexer<'a>>;
+    /// The location of the offending input, which is empty at the end of the
+    /// input.
+    pub span: Span,
 
-/// Parsing errors.
-#[derive(Debug)]
-pub enum Error {
+    /// The tokens that would have been valid at the location of the error.
+    pub expected: Vec<String>,
+
+    /// Input that may have been intended in place of the offending input.
+    pub suggestion: Option<String>,
+}
+
+/// Kinds of parsing errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, rename = "DiceSyntaxErrorKind")]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorKind {
     /// A token was encountered at an unexpected position.
-    UnexpectedToken(String),
+    UnexpectedToken,
 
     /// The end of the input was reached unexpectedly.
-    UnexpectedEnd(String),
+    UnexpectedEnd,
 
     /// A die with an invalid number of sides was encountered.
-    InvalidDie(String),
+    InvalidDie,
+
+    /// A closing `)`, `]` or `}` was encountered that did not match the
+    /// opening `(`, `[` or `{`.
+    MismatchedParentheses,
+
+    /// An expression was repeated too few or too many times.
+    InvalidRepeat,
+
+    /// A function was called with the wrong number of arguments.
+    InvalidCall,
+
+    /// An invalid character was encountered by the lexer.
+    InvalidCharacter,
 
-    /// A closing `)` or `]` was encountered that did not match the opening `(`
-    /// or `[`.
-    MismatchedParentheses(String),
+    /// An invalid word was encountered by the lexer.
+    InvalidWord,
 
-    /// An error occurred in the lexer.
-    LexError(LexError),
+    /// An integer too large to represent was encountered by the lexer.
+    InvalidInteger,
+
+    /// The expression was nested more deeply than [`MAX_DEPTH`] or
+    /// [`MAX_NESTING`] allows.
+    TooDeep,
+
+    /// Successes were counted against a target that no face of the die can
+    /// meet.
+    ImpossibleTarget,
+}
+
+impl Error {
+    /// Creates an error located at `span`.
+    fn new(kind: ErrorKind, message: impl Into<String>, span: Span) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            span,
+            expected: Vec::new(),
+            suggestion: None,
+        }
+    }
+
+    /// Creates an error located at the next token, or at the end of the input.
+    fn at(lexer: &LookaheadLexer, kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self::new(kind, message, lexer.inner().span())
+    }
+
+    /// Lists the tokens that would have been valid in place of the offending
+    /// input.
+    fn expecting(self, expected: &[&str]) -> Self {
+        Self {
+            expected: expected.iter().map(|token| token.to_string()).collect(),
+            ..self
+        }
+    }
+
+    /// Suggests input that may have been intended.
+    fn suggesting(self, suggestion: impl Into<String>) -> Self {
+        Self {
+            suggestion: Some(suggestion.into()),
+            ..self
+        }
+    }
 }
 
 type Result = std::result::Result<Box<Node>, Error>;
 type ResultOption = std::result::Result<Option<Box<Node>>, Error>;
+type ResultTarget = std::result::Result<Option<Target>, Error>;
+type ResultBatch = std::result::Result<Vec<Box<Node>>, Error>;
+
+/// The greatest number of times an expression may be repeated, e.g. `100x(d20)`.
+pub const MAX_REPEAT: i32 = 100;
+
+/// The greatest depth of the syntax tree of an expression. Deeper expressions
+/// are rejected as they are parsed, so that no later pass over the tree has to
+/// guard against them.
+pub const MAX_DEPTH: usize = 256;
+
+/// The greatest depth to which parentheses, negations, calls, conditionals and
+/// selections may nest within each other, which bounds the recursion of the
+/// parser itself.
+pub const MAX_NESTING: usize = 64;
 
 /// Parse a dice expression into an abstract syntax tree.
 pub fn parse<'a>(input: &'a str) -> Result {
-    let lexer = Lexer::new(input);
-    let mut lexer = Lookahead::new(lexer);
+    let mut lexer = LookaheadLexer::new(input);
     let root = parse_root(&mut lexer)?;
+    parse_end(&mut lexer)?;
+    check_depth(&root, input)?;
+    Ok(root)
+}
+
+/// Parse a batch of independent dice expressions, such as `6x(4d6kh3)` or
+/// `{d20 + 5, d20 + 3}`, into one abstract syntax tree per expression. A
+/// single expression is parsed as a batch of one.
+pub fn parse_batch(input: &str) -> ResultBatch {
+    let mut lexer = LookaheadLexer::new(input);
+    let batch = parse_batch_rule(&mut lexer)?;
+    parse_end(&mut lexer)?;
+    for root in &batch {
+        check_depth(root, input)?;
+    }
+    Ok(batch)
+}
+
+/// Check that the syntax tree rooted at `root` is at most [`MAX_DEPTH`] levels
+/// deep. The tree is walked without recursion, as a long chain of operators
+/// such as `1 + 1 + ... + 1` nests deeply without nesting the parser.
+fn check_depth(root: &Node, input: &str) -> std::result::Result<(), Error> {
+    let mut stack = vec![(root, 1)];
+
+    while let Some((node, depth)) = stack.pop() {
+        if depth > MAX_DEPTH {
+            let span = Span {
+                start: 0,
+                end: input.len(),
+            };
+            return Err(too_deep(MAX_DEPTH, span));
+        }
+        stack.extend(node.children().into_iter().map(|child| (child, depth + 1)));
+    }
+
+    Ok(())
+}
+
+/// Parse a production nested within the one being parsed with `parse`,
+/// failing if it is nested more than [`MAX_NESTING`] levels deep.
+fn parse_nested<T>(
+    lexer: &mut LookaheadLexer,
+    parse: impl FnOnce(&mut LookaheadLexer) -> std::result::Result<T, Error>,
+) -> std::result::Result<T, Error> {
+    if lexer.depth >= MAX_NESTING {
+        return Err(too_deep(MAX_NESTING, lexer.inner().span()));
+    }
+
+    lexer.depth += 1;
+    let result = parse(lexer);
+    lexer.depth -= 1;
+    result
+}
+
+/// Creates the error for an expression nested more than `max` levels deep.
+fn too_deep(max: usize, span: Span) -> Error {
+    Error::new(
+        ErrorKind::TooDeep,
+        format!("Expression is too deeply nested (at most {max} levels)"),
+        span,
+    )
+}
+
+/// Check that the whole input has been consumed.
+fn parse_end(lexer: &mut LookaheadLexer) -> std::result::Result<(), Error> {
+    match lexer.peek() {
+        Some(Ok(token)) => Err(Error::at(
+            lexer,
+            ErrorKind::UnexpectedToken,
+            format!("Unexpected leftover token: '{token}'"),
+        )
+        .expecting(EXPECTED_OPERATOR)),
+        Some(Err(err)) => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Parse the production rules:
+/// ```ebnf
+/// batch = repeat | list | root;
+/// repeat = integer, "x", "(", conditional, ")";
+/// list = "{", conditional, { ",", conditional }, "}";
+/// ```
+///
+/// A repeat is recognized by the `x` following an integer root.
+fn parse_batch_rule(lexer: &mut LookaheadLexer) -> ResultBatch {
+    if let Some(Ok(Token::Open('{'))) = lexer.peek() {
+        lexer.next();
+        let mut list = vec![parse_conditional(lexer)?];
+
+        loop {
+            match lexer.peek().cloned() {
+                Some(Ok(Token::Comma)) => {
+                    lexer.next();
+                    list.push(parse_conditional(lexer)?);
+                }
+                Some(Ok(Token::Close('}'))) => {
+                    lexer.next();
+                    return Ok(list);
+                }
+                Some(Ok(Token::Close(close_ch))) => {
+                    return Err(Error::at(
+                        lexer,
+                        ErrorKind::MismatchedParentheses,
+                        format!("Closing '{close_ch}' does not match opening '{{'"),
+                    )
+                    .expecting(&[",", "}"])
+                    .suggesting("}"))
+                }
+                Some(Ok(other)) => {
+                    return Err(Error::at(
+                        lexer,
+                        ErrorKind::UnexpectedToken,
+                        format!("'{other}' unexpected in list"),
+                    )
+                    .expecting(&[",", "}"]))
+                }
+                Some(Err(err)) => return Err(err.into()),
+                None => {
+                    return Err(Error::at(
+                        lexer,
+                        ErrorKind::UnexpectedEnd,
+                        "Expression ended without closing '}'".to_string(),
+                    )
+                    .expecting(&[",", "}"]))
+                }
+            }
+        }
+    }
 
+    let count_span = lexer.inner().span();
+    let root = parse_root(lexer)?;
+    let (Node::Lit { value: count }, Some(Ok(Token::Word("x")))) = (root.as_ref(), lexer.peek())
+    else {
+        return Ok(vec![root]);
+    };
+
+    if !(1..=MAX_REPEAT).contains(count) {
+        return Err(Error::new(
+            ErrorKind::InvalidRepeat,
+            format!("Cannot repeat an expression {count} times (must be from 1 to {MAX_REPEAT})"),
+            count_span,
+        ));
+    }
+
+    lexer.next();
     match lexer.peek() {
-        Some(Ok(token)) => Err(Error::UnexpectedToken(format!(
-            "Unexpected leftover token: '{token}'"
-        ))),
+        Some(Ok(Token::Open('('))) => {}
+        Some(Ok(other)) => {
+            return Err(Error::at(
+                lexer,
+                ErrorKind::UnexpectedToken,
+                format!("'{other}' unexpected in repeat, expected '('"),
+            )
+            .expecting(&["("]))
+        }
+        Some(Err(err)) => return Err(err.into()),
+        None => {
+            return Err(Error::at(
+                lexer,
+                ErrorKind::UnexpectedEnd,
+                "Expression ended before repeated expression".to_string(),
+            )
+            .expecting(&["("]))
+        }
+    }
+
+    let right = parse_factor(lexer)?;
+    Ok(vec![right; *count as usize])
+}
+
+/// Check that the next token is `expected` and consume it.
+fn parse_expected(
+    lexer: &mut LookaheadLexer,
+    expected: Token,
+    context: &str,
+) -> std::result::Result<(), Error> {
+    match lexer.peek() {
+        Some(Ok(token)) if *token == expected => {
+            lexer.next();
+            Ok(())
+        }
+        Some(Ok(other)) => Err(Error::at(
+            lexer,
+            ErrorKind::UnexpectedToken,
+            format!("'{other}' unexpected in {context}, expected '{expected}'"),
+        )
+        .expecting(&[expected.to_string().as_str()])),
         Some(Err(err)) => Err(err.into()),
-        _ => Ok(root),
+        None => Err(Error::at(
+            lexer,
+            ErrorKind::UnexpectedEnd,
+            format!("Expression ended without '{expected}'"),
+        )
+        .expecting(&[expected.to_string().as_str()])),
     }
 }
 
 /// Parse the production rule:
 /// ```ebnf
-/// root = sum;
+/// root = conditional;
 /// ```
 fn parse_root(lexer: &mut LookaheadLexer) -> Result {
-    parse_sum(lexer)
+    parse_conditional(lexer)
 }
 
 /// Parse the production rule:
 /// ```ebnf
-/// sum = term, { ("+" | "-"), term };
+/// conditional = condition, ["?", conditional, ":", conditional];
 /// ```
+fn parse_conditional(lexer: &mut LookaheadLexer) -> Result {
+    let condition = parse_condition(lexer)?;
+
+    match lexer.peek() {
+        Some(Ok(Token::Question)) => {
+            lexer.next();
+        }
+        Some(Err(err)) => return Err(err.into()),
+        _ => return Ok(condition),
+    }
+
+    let then = parse_nested(lexer, parse_conditional)?;
+    parse_expected(lexer, Token::Colon, "conditional")?;
+    let otherwise = parse_nested(lexer, parse_conditional)?;
+
+    Ok(Box::new(Node::Cond {
+        condition,
+        then,
+        otherwise,
+    }))
+}
+
+/// Parse the production rule:
+/// ```ebnf
+/// condition = sum, [comparison, sum];
+/// ```
+///
+/// A comparison directly after a roll is parsed as a [`count`](parse_count)
+/// of successes, so a roll must be parenthesized to compare its total, as in
+/// `(1d20) >= 15`. A count that no die could ever meet, as in `2d6 > 7`, is
+/// rejected with that suggestion.
+fn parse_condition(lexer: &mut LookaheadLexer) -> Result {
+    let left = parse_sum(lexer)?;
+
+    let comparison = match lexer.peek() {
+        Some(Ok(Token::Equal)) => Comparison::Equal,
+        Some(Ok(Token::Less)) => Comparison::Less,
+        Some(Ok(Token::LessEqual)) => Comparison::LessOrEqual,
+        Some(Ok(Token::Greater)) => Comparison::Greater,
+        Some(Ok(Token::GreaterEqual)) => Comparison::GreaterOrEqual,
+        Some(Err(err)) => return Err(err.into()),
+        _ => return Ok(left),
+    };
+
+    lexer.next();
+    let right = parse_sum(lexer)?;
+
+    Ok(Box::new(Node::Compare {
+        comparison,
+        left,
+        right,
+    }))
+}
+
+/// Parse the production rule:
+/// ```ebnf
+/// sum = term, { ("+" | "-"), term | "-", "f", target };
+/// ```
+///
+/// The second form is an alternative way to write the failure target of a
+/// preceding [`count`](parse_count) and is only valid after one.
 fn parse_sum(lexer: &mut LookaheadLexer) -> Result {
     let mut left = parse_term(lexer)?;
 
@@ -74,6 +445,29 @@ fn parse_sum(lexer: &mut LookaheadLexer) -> Result {
             }
             Some(Ok(Token::Minus)) => {
                 lexer.next();
+
+                if let Some(Ok(Token::Word("f"))) = lexer.peek() {
+                    left = match *left {
+                        Node::Count {
+                            roll,
+                            success,
+                            failure: None,
+                        } => Box::new(Node::Count {
+                            roll,
+                            success,
+                            failure: Some(parse_failure(lexer)?),
+                        }),
+                        _ => {
+                            return Err(Error::at(
+                                lexer,
+                                ErrorKind::UnexpectedToken,
+                                "'f' is only valid after counting successes".to_string(),
+                            ))
+                        }
+                    };
+                    continue;
+                }
+
                 let right = parse_term(lexer)?;
                 left = Box::new(Node::Sub { left, right });
             }
@@ -87,22 +481,23 @@ fn parse_sum(lexer: &mut LookaheadLexer) -> Result {
 
 /// Parse the production rule:
 /// ```ebnf
-/// term = factor, { ("*" | "/"), factor };
+/// term = factor, [label], { ("*" | "/"), factor, [label] };
 /// ```
 fn parse_term(lexer: &mut LookaheadLexer) -> Result {
-    let mut left = parse_factor(lexer)?;
+    let factor = parse_factor(lexer)?;
+    let mut left = parse_label(lexer, factor)?;
 
     loop {
         match lexer.peek() {
             Some(Ok(Token::Times)) => {
                 lexer.next();
                 let right = parse_factor(lexer)?;
-                left = Box::new(Node::Mul { left, right });
+                left = parse_label(lexer, Box::new(Node::Mul { left, right }))?;
             }
             Some(Ok(Token::Divide)) => {
                 lexer.next();
                 let right = parse_factor(lexer)?;
-                left = Box::new(Node::Div { left, right });
+                left = parse_label(lexer, Box::new(Node::Div { left, right }))?;
             }
             Some(Err(err)) => return Err(err.into()),
             _ => break,
@@ -112,41 +507,65 @@ fn parse_term(lexer: &mut LookaheadLexer) -> Result {
     Ok(left)
 }
 
+/// Parse the production rule, where the label applies to the preceding
+/// `node`:
+/// ```ebnf
+/// label = "[", /[A-Za-z][A-Za-z _-]*/, "]";
+/// ```
+fn parse_label(lexer: &mut LookaheadLexer, node: Box<Node>) -> Result {
+    match lexer.peek() {
+        Some(Ok(Token::Label(label))) => {
+            let label = label.to_string();
+            lexer.next();
+            Ok(Box::new(Node::Tag { label, right: node }))
+        }
+        Some(Err(err)) => Err(err.into()),
+        _ => Ok(node),
+    }
+}
+
 /// Parse the production rule:
 /// ```ebnf
-/// factor = "(", sum, ")" | negation | integer | roll;
+/// factor = "(", conditional, ")" | call | hit | negation | integer | variable | roll, [count];
 /// ```
 fn parse_factor(lexer: &mut LookaheadLexer) -> Result {
     let token = lexer.peek().cloned();
 
     match token {
-        Some(Ok(Token::Open(open_ch))) => {
+        Some(Ok(Token::Open(open_ch @ ('(' | '[')))) => {
+            let close = if open_ch == '(' { ")" } else { "]" };
             lexer.next();
-            let sum = parse_sum(lexer)?;
+            let sum = parse_nested(lexer, parse_conditional)?;
 
             match lexer.peek().cloned() {
                 Some(Ok(Token::Close(close_ch))) => {
+                    let span = lexer.inner().span();
                     lexer.next();
                     match (open_ch, close_ch) {
                         ('(', ')') => Ok(sum),
                         ('[', ']') => Ok(sum),
-                        _ => Err(Error::MismatchedParentheses(format!(
-                            "Closing '{close_ch}' does not match opening '{open_ch}'"
-                        ))),
+                        _ => Err(Error::new(
+                            ErrorKind::MismatchedParentheses,
+                            format!("Closing '{close_ch}' does not match opening '{open_ch}'"),
+                            span,
+                        )
+                        .expecting(&[close])
+                        .suggesting(close)),
                     }
                 }
-                Some(Ok(other)) => Err(Error::UnexpectedToken(format!(
-                    "'{other}' unexpected in parenthetical",
-                ))),
+                Some(Ok(other)) => Err(Error::at(
+                    lexer,
+                    ErrorKind::UnexpectedToken,
+                    format!("'{other}' unexpected in parenthetical",),
+                )
+                .expecting(&[close])),
                 Some(Err(err)) => return Err(err.into()),
-                None => Err(Error::UnexpectedEnd(format!(
-                    "Expression ended without closing '{}'",
-                    match open_ch {
-                        '(' => ')',
-                        '[' => ']',
-                        _ => unreachable!(),
-                    }
-                ))),
+                None => Err(Error::at(
+                    lexer,
+                    ErrorKind::UnexpectedEnd,
+                    format!("Expression ended without closing '{close}'"),
+                )
+                .expecting(&[close])),
             }
         }
 
@@ -154,32 +573,135 @@ fn parse_factor(lexer: &mut LookaheadLexer) -> Result {
             let token = lexer.next();
 
             match &token {
-                Some(Ok(Token::Word("d"))) => parse_roll(lexer, n),
+                Some(Ok(Token::Word("d" | "dF"))) => {
+                    let roll = parse_roll(lexer, n)?;
+                    parse_count(lexer, roll)
+                }
                 _ => Ok(Box::new(Node::Lit { value: n })),
             }
         }
 
-        Some(Ok(Token::Word("d"))) => parse_roll(lexer, 1),
+        Some(Ok(Token::Variable(name))) => {
+            lexer.next();
+            Ok(Box::new(Node::Var {
+                name: name.to_string(),
+            }))
+        }
+
+        Some(Ok(Token::Word("hit"))) => parse_nested(lexer, parse_hit),
+
+        Some(Ok(Token::Word(name))) if Function::from_name(name).is_some() => {
+            parse_nested(lexer, parse_call)
+        }
+
+        Some(Ok(Token::Word("d" | "dF"))) => {
+            let roll = parse_roll(lexer, 1)?;
+            parse_count(lexer, roll)
+        }
 
         Some(Ok(Token::Minus)) => {
             lexer.next();
-            let right = parse_factor(lexer)?;
+            let right = parse_nested(lexer, parse_factor)?;
             Ok(Box::new(Node::Neg { right }))
         }
 
         Some(Err(err)) => return Err(err.into()),
 
-        Some(Ok(other)) => Err(Error::UnexpectedToken(format!(
-            "'{other}' unexpected in factor",
-        ))),
+        Some(Ok(other)) => Err(Error::at(
+            lexer,
+            ErrorKind::UnexpectedToken,
+            format!("'{other}' unexpected in factor",),
+        )
+        .expecting(EXPECTED_FACTOR)),
+
+        None => Err(Error::at(
+            lexer,
+            ErrorKind::UnexpectedEnd,
+            "Unexpected end of input".to_string(),
+        )
+        .expecting(EXPECTED_FACTOR)),
+    }
+}
+
+/// Parse the production rules:
+/// ```ebnf
+/// call = function, "(", conditional, { ",", conditional }, ")";
+/// function = "min" | "max" | "floor" | "ceil" | "round" | "abs";
+/// ```
+fn parse_call(lexer: &mut LookaheadLexer) -> Result {
+    let Some(Ok(Token::Word(name))) = lexer.peek().cloned() else {
+        return Err(Error::at(
+            lexer,
+            ErrorKind::UnexpectedEnd,
+            "Unexpected end of input".to_string(),
+        ));
+    };
+    let Some(function) = Function::from_name(name) else {
+        return Err(Error::at(
+            lexer,
+            ErrorKind::UnexpectedToken,
+            format!("Unknown function '{name}'"),
+        ));
+    };
+
+    let start = lexer.inner().span().start;
+    lexer.next();
+    parse_expected(lexer, Token::Open('('), name)?;
+    let mut args = vec![*parse_conditional(lexer)?];
 
-        None => Err(Error::UnexpectedEnd("Unexpected end of input".to_string())),
+    while let Some(Ok(Token::Comma)) = lexer.peek() {
+        lexer.next();
+        args.push(*parse_conditional(lexer)?);
     }
+
+    let end = lexer.inner().span().end;
+    parse_expected(lexer, Token::Close(')'), name)?;
+
+    let (least, greatest) = function.arity();
+    if args.len() < least || args.len() > greatest {
+        let expected = if least == greatest {
+            least.to_string()
+        } else {
+            format!("at least {least}")
+        };
+        return Err(Error::new(
+            ErrorKind::InvalidCall,
+            format!(
+                "'{name}' takes {expected} argument(s) but was given {}",
+                args.len()
+            ),
+            Span { start, end },
+        ));
+    }
+
+    Ok(Box::new(Node::Call { function, args }))
 }
 
 /// Parse the production rule:
 /// ```ebnf
-/// roll = [integer], "d", [integer], [selection];
+/// hit = "hit", "(", conditional, ",", conditional, ",", conditional, ")";
+/// ```
+fn parse_hit(lexer: &mut LookaheadLexer) -> Result {
+    lexer.next();
+    parse_expected(lexer, Token::Open('('), "hit")?;
+    let attack = parse_conditional(lexer)?;
+    parse_expected(lexer, Token::Comma, "hit")?;
+    let target = parse_conditional(lexer)?;
+    parse_expected(lexer, Token::Comma, "hit")?;
+    let damage = parse_conditional(lexer)?;
+    parse_expected(lexer, Token::Close(')'), "hit")?;
+
+    Ok(Box::new(Node::Hit {
+        attack,
+        target,
+        damage,
+    }))
+}
+
+/// Parse the production rule:
+/// ```ebnf
+/// roll = [integer], ("d", [sides] | "dF"), [selection];
+/// sides = integer | "%" | "(", sum, ")";
 /// ```
 fn parse_roll(lexer: &mut LookaheadLexer, count: i32) -> Result {
     let token = lexer.peek();
@@ -187,49 +709,65 @@ fn parse_roll(lexer: &mut LookaheadLexer, count: i32) -> Result {
         Some(Ok(Token::Word("d"))) => {
             let token = lexer.next();
 
-            match token {
-                Some(Ok(Token::Integer(sides))) => match sides {
-                    4 | 6 | 8 | 10 | 12 | 20 | 100 => {
-                        lexer.next();
-                        let select = parse_selection(lexer)?;
-                        Ok(Box::new(Node::Roll {
-                            count: Box::new(Node::Lit { value: count }),
-                            sides: Box::new(Node::Lit { value: sides }),
-                            select,
-                        }))
+            let sides = match token {
+                Some(Ok(Token::Integer(sides))) => {
+                    if sides < 1 {
+                        return Err(Error::at(
+                            lexer,
+                            ErrorKind::InvalidDie,
+                            format!("Invalid die: d{sides}"),
+                        ));
                     }
-                    _ => Err(Error::InvalidDie(format!("Invalid die: d{sides}"))),
-                },
+
+                    lexer.next();
+                    Box::new(Node::Lit { value: sides })
+                }
                 Some(Ok(Token::Percent)) => {
                     lexer.next();
-                    let select = parse_selection(lexer)?;
-                    Ok(Box::new(Node::Roll {
-                        count: Box::new(Node::Lit { value: count }),
-                        sides: Box::new(Node::Lit { value: 100 }),
-                        select,
-                    }))
+                    Box::new(Node::Lit { value: 100 })
                 }
+                Some(Ok(Token::Open('('))) => parse_factor(lexer)?,
 
                 Some(Err(err)) => return Err(err.into()),
 
-                _ => {
-                    let select = parse_selection(lexer)?;
-                    Ok(Box::new(Node::Roll {
-                        count: Box::new(Node::Lit { value: count }),
-                        sides: Box::new(Node::Lit { value: 6 }),
-                        select,
-                    }))
-                }
-            }
+                _ => Box::new(Node::Lit { value: 6 }),
+            };
+
+            let select = parse_selection(lexer)?;
+            Ok(Box::new(Node::Roll {
+                count: Box::new(Node::Lit { value: count }),
+                sides,
+                fudge: false,
+                select,
+            }))
+        }
+
+        Some(Ok(Token::Word("dF"))) => {
+            lexer.next();
+            let select = parse_selection(lexer)?;
+            Ok(Box::new(Node::Roll {
+                count: Box::new(Node::Lit { value: count }),
+                sides: Box::new(Node::Lit { value: 3 }),
+                fudge: true,
+                select,
+            }))
         }
 
         Some(Err(err)) => return Err(err.into()),
 
-        Some(Ok(other)) => Err(Error::UnexpectedToken(format!(
-            "'{other}' unexpected in roll",
-        ))),
+        Some(Ok(other)) => Err(Error::at(
+            lexer,
+            ErrorKind::UnexpectedToken,
+            format!("'{other}' unexpected in roll",),
+        )
+        .expecting(&["d", "dF"])),
 
-        None => Err(Error::UnexpectedEnd("Unexpected end of input".to_string())),
+        None => Err(Error::at(
+            lexer,
+            ErrorKind::UnexpectedEnd,
+            "Unexpected end of input".to_string(),
+        )
+        .expecting(&["d", "dF"])),
     }
 }
 
@@ -243,7 +781,11 @@ fn parse_roll(lexer: &mut LookaheadLexer, count: i32) -> Result {
 ///         "dh", integer |
 ///         "dl", integer |
 ///         "adv" | "ad" |
-///         "dis" | "da"
+///         "dis" | "da" |
+///         "!", [target] |
+///         "!!", [target] |
+///         "r", target |
+///         "ro", target
 ///     ), [selection];
 /// ```
 fn parse_selection(lexer: &mut LookaheadLexer) -> ResultOption {
@@ -262,13 +804,13 @@ fn parse_selection(lexer: &mut LookaheadLexer) -> ResultOption {
                     lexer.next();
                     (
                         Some(Box::new(Node::Lit { value: n })),
-                        parse_selection(lexer)?,
+                        parse_nested(lexer, parse_selection)?,
                     )
                 }
 
                 Some(Err(err)) => return Err(err.into()),
 
-                _ => (None, parse_selection(lexer)?),
+                _ => (None, parse_nested(lexer, parse_selection)?),
             };
 
             match selection {
@@ -296,7 +838,7 @@ fn parse_selection(lexer: &mut LookaheadLexer) -> ResultOption {
             lexer.next();
             Ok(Some(Box::new(Node::Select {
                 selection: Selection::Advantage,
-                next: parse_selection(lexer)?,
+                next: parse_nested(lexer, parse_selection)?,
             })))
         }
 
@@ -304,7 +846,59 @@ fn parse_selection(lexer: &mut LookaheadLexer) -> ResultOption {
             lexer.next();
             Ok(Some(Box::new(Node::Select {
                 selection: Selection::Disadvantage,
-                next: parse_selection(lexer)?,
+                next: parse_nested(lexer, parse_selection)?,
+            })))
+        }
+
+        Some(Ok(Token::Bang)) => {
+            let compound = matches!(lexer.next(), Some(Ok(Token::Bang)));
+            if compound {
+                lexer.next();
+            }
+
+            let target = parse_target(lexer)?;
+            let selection = if compound {
+                Selection::CompoundExplode { target }
+            } else {
+                Selection::Explode { target }
+            };
+
+            Ok(Some(Box::new(Node::Select {
+                selection,
+                next: parse_nested(lexer, parse_selection)?,
+            })))
+        }
+
+        Some(Ok(Token::Word("r"))) | Some(Ok(Token::Word("ro"))) => {
+            let once = matches!(token, Some(Ok(Token::Word("ro"))));
+            lexer.next();
+
+            let Some(target) = parse_target(lexer)? else {
+                return match lexer.peek() {
+                    Some(Ok(other)) => Err(Error::at(
+                        lexer,
+                        ErrorKind::UnexpectedToken,
+                        format!("'{other}' unexpected in reroll",),
+                    )
+                    .expecting(EXPECTED_TARGET)),
+                    _ => Err(Error::at(
+                        lexer,
+                        ErrorKind::UnexpectedEnd,
+                        "Expression ended without a reroll target".to_string(),
+                    )
+                    .expecting(EXPECTED_TARGET)),
+                };
+            };
+
+            let selection = if once {
+                Selection::RerollOnce { target }
+            } else {
+                Selection::Reroll { target }
+            };
+
+            Ok(Some(Box::new(Node::Select {
+                selection,
+                next: parse_nested(lexer, parse_selection)?,
             })))
         }
 
@@ -314,35 +908,254 @@ fn parse_selection(lexer: &mut LookaheadLexer) -> ResultOption {
     }
 }
 
-impl StdError for Error {
-    fn source(&self) -> Option<&(dyn StdError + 'static)> {
-        match self {
-            Error::LexError(error) => Some(error),
-            _ => None,
+/// Parse the production rule:
+/// ```ebnf
+/// count = ("cs", target | comparison, integer), ["f", target];
+/// ```
+///
+/// If neither a `cs` nor a comparison follows, `roll` is returned unchanged.
+fn parse_count(lexer: &mut LookaheadLexer, roll: Box<Node>) -> Result {
+    let span = lexer.inner().span();
+    let explicit = matches!(lexer.peek(), Some(Ok(Token::Word("cs"))));
+    let success = match lexer.peek() {
+        Some(Ok(Token::Word("cs"))) => {
+            lexer.next();
+            parse_target(lexer)?
+        }
+        Some(Ok(
+            Token::Equal | Token::Less | Token::LessEqual | Token::Greater | Token::GreaterEqual,
+        )) => parse_target(lexer)?,
+        Some(Err(err)) => return Err(err.into()),
+        _ => return Ok(roll),
+    };
+
+    let Some(success) = success else {
+        return match lexer.peek() {
+            Some(Ok(other)) => Err(Error::at(
+                lexer,
+                ErrorKind::UnexpectedToken,
+                format!("'{other}' unexpected in success target",),
+            )
+            .expecting(EXPECTED_TARGET)),
+            _ => Err(Error::at(
+                lexer,
+                ErrorKind::UnexpectedEnd,
+                "Expression ended without a success target".to_string(),
+            )
+            .expecting(EXPECTED_TARGET)),
+        };
+    };
+
+    if !reachable(&roll, &success) {
+        let mut written = String::new();
+        // Writing to a string cannot fail.
+        let _ = PP::new(&mut written).write(&Node::Count {
+            roll: roll.clone(),
+            success: success.clone(),
+            failure: None,
+        });
+        let error = Error::new(
+            ErrorKind::ImpossibleTarget,
+            format!("No die in '{written}' can be counted as a success"),
+            span,
+        );
+
+        if explicit {
+            return Err(error);
+        }
+
+        // A comparison rather than `cs` was most likely meant to compare the
+        // total of the roll.
+        let mut total = String::new();
+        let _ = PP::new(&mut total).write(&Node::Compare {
+            comparison: success.comparison,
+            left: roll,
+            right: success.value,
+        });
+        return Err(error.suggesting(total));
+    }
+
+    let failure = match lexer.peek() {
+        Some(Ok(Token::Word("f"))) => Some(parse_failure(lexer)?),
+        _ => None,
+    };
+
+    Ok(Box::new(Node::Count {
+        roll,
+        success,
+        failure,
+    }))
+}
+
+/// Returns whether some die of `roll` could meet `target`. Dice whose sides
+/// are not a literal, or that compound when they explode, are assumed to.
+fn reachable(roll: &Node, target: &Target) -> bool {
+    let (
+        Node::Roll {
+            sides,
+            fudge,
+            select,
+            ..
+        },
+        Node::Lit { value },
+    ) = (roll, target.value.as_ref())
+    else {
+        return true;
+    };
+    let Node::Lit { value: sides } = sides.as_ref() else {
+        return true;
+    };
+
+    let mut next = select.as_deref();
+    while let Some(Node::Select {
+        selection,
+        next: rest,
+    }) = next
+    {
+        if let Selection::CompoundExplode { .. } = selection {
+            return true;
+        }
+        next = rest.as_deref();
+    }
+
+    let faces = faces(*sides, *fudge);
+    match target.comparison {
+        Comparison::Equal => faces.contains(value),
+        comparison => {
+            compare(comparison, *faces.start(), *value) || compare(comparison, *faces.end(), *value)
+        }
+    }
+}
+
+/// Parse the failure target of a count:
+/// ```ebnf
+/// "f", target
+/// ```
+fn parse_failure(lexer: &mut LookaheadLexer) -> std::result::Result<Target, Error> {
+    lexer.next();
+
+    match parse_target(lexer)? {
+        Some(target) => Ok(target),
+        None => match lexer.peek() {
+            Some(Ok(other)) => Err(Error::at(
+                lexer,
+                ErrorKind::UnexpectedToken,
+                format!("'{other}' unexpected in failure target",),
+            )
+            .expecting(EXPECTED_TARGET)),
+            _ => Err(Error::at(
+                lexer,
+                ErrorKind::UnexpectedEnd,
+                "Expression ended without a failure target".to_string(),
+            )
+            .expecting(EXPECTED_TARGET)),
+        },
+    }
+}
+
+/// Parse the production rule:
+/// ```ebnf
+/// target = [comparison], integer;
+/// comparison = "=" | "<" | "<=" | ">" | ">=";
+/// ```
+fn parse_target(lexer: &mut LookaheadLexer) -> ResultTarget {
+    let comparison = match lexer.peek() {
+        Some(Ok(Token::Equal)) => Comparison::Equal,
+        Some(Ok(Token::Less)) => Comparison::Less,
+        Some(Ok(Token::LessEqual)) => Comparison::LessOrEqual,
+        Some(Ok(Token::Greater)) => Comparison::Greater,
+        Some(Ok(Token::GreaterEqual)) => Comparison::GreaterOrEqual,
+        Some(Ok(Token::Integer(n))) => {
+            let value = Box::new(Node::Lit { value: *n });
+            lexer.next();
+            return Ok(Some(Target {
+                comparison: Comparison::Equal,
+                value,
+            }));
         }
+        Some(Err(err)) => return Err(err.into()),
+        _ => return Ok(None),
+    };
+
+    match lexer.next() {
+        Some(Ok(Token::Integer(n))) => {
+            lexer.next();
+            Ok(Some(Target {
+                comparison,
+                value: Box::new(Node::Lit { value: n }),
+            }))
+        }
+        Some(Ok(other)) => Err(Error::at(
+            lexer,
+            ErrorKind::UnexpectedToken,
+            format!("'{other}' unexpected in target",),
+        )
+        .expecting(&["integer"])),
+        Some(Err(err)) => Err(err.into()),
+        None => Err(Error::at(
+            lexer,
+            ErrorKind::UnexpectedEnd,
+            "Expression ended without a target value".to_string(),
+        )
+        .expecting(&["integer"])),
     }
 }
 
+impl<'a> LookaheadLexer<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            tokens: Lookahead::new(Lexer::new(input)),
+            depth: 0,
+        }
+    }
+}
+
+impl<'a> Deref for LookaheadLexer<'a> {
+    type Target = Lookahead<Lexer<'a>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tokens
+    }
+}
+
+impl DerefMut for LookaheadLexer<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.tokens
+    }
+}
+
+impl StdError for Error {}
+
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        match self {
-            Error::UnexpectedToken(message) => write!(f, "{message}",),
-            Error::UnexpectedEnd(message) => write!(f, "{message}"),
-            Error::InvalidDie(message) => write!(f, "{message}"),
-            Error::MismatchedParentheses(message) => write!(f, "{message}",),
-            Error::LexError(error) => write!(f, "{error}"),
+        write!(f, "{}", self.message)?;
+
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, " (did you mean '{suggestion}'?)")?;
         }
+
+        Ok(())
     }
 }
 
 impl From<LexError> for Error {
     fn from(error: LexError) -> Self {
-        Error::LexError(error)
+        let kind = match error.kind {
+            LexErrorKind::InvalidCharacter(_) => ErrorKind::InvalidCharacter,
+            LexErrorKind::InvalidWord(_) => ErrorKind::InvalidWord,
+            LexErrorKind::ParseIntError(_) => ErrorKind::InvalidInteger,
+        };
+
+        let parse_error = Error::new(kind, error.to_string(), error.span);
+        match error.suggestion() {
+            Some(suggestion) => parse_error.suggesting(suggestion),
+            None => parse_error,
+        }
     }
 }
 
 impl From<&LexError> for Error {
     fn from(error: &LexError) -> Self {
-        Error::LexError(error.clone())
+        error.clone().into()
     }
 }
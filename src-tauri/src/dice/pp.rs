@@ -3,26 +3,167 @@
 
 //! This module contains a pretty-printer for the dice expressions that
 //! traverses an AST and outputs a string representation of the expression.
+//!
+//! In its annotated mode, the pretty-printer is driven by the results of an
+//! evaluation: each roll is replaced by its total and recorded as a [`Step`]
+//! listing its dice, so that a roll such as `4d6kh3 + 2` can be explained as
+//! `4d6kh3 → [6, 5, 3, ~1~] = 14; 14 + 2 = 16`.
 
-use std::fmt::{Error as FmtError, Write};
+use std::fmt::{Display, Error as FmtError, Formatter, Result as FmtResult, Write};
 
-use super::ast::{Node, Selection};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use super::ast::{Comparison, Function, Node, Selection, Target};
+use super::eval::{Branch, DieRoll, PoolTotal, Resolver};
+
+/// The loosest-binding construct that may be written without parentheses at
+/// some position in an expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Precedence {
+    /// Any expression, including a conditional.
+    Conditional,
+
+    /// A comparison or anything binding more tightly.
+    Condition,
+
+    /// A sum or anything binding more tightly.
+    Sum,
+}
+
+/// A step in the explanation of a roll: either a roll of dice and the total
+/// they contributed, or the arithmetic that combines the totals of earlier
+/// steps into the result.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, rename = "RollStep")]
+#[serde(rename_all = "camelCase")]
+pub struct Step {
+    /// The part of the expression evaluated by this step, with the totals of
+    /// earlier steps in place of their rolls.
+    pub expression: String,
+
+    /// The dice rolled by this step, including any that were discarded.
+    pub dice: Vec<DieRoll>,
+
+    /// The result of this step.
+    pub value: i32,
+}
+
+/// The results of an evaluation that drive the annotated mode of the
+/// pretty-printer.
+pub struct Annotation<'e> {
+    /// Every die rolled during the evaluation.
+    rolls: &'e [DieRoll],
+
+    /// The dice and total of each roll, in the order they were completed.
+    pools: &'e [PoolTotal],
+
+    /// The branches taken by conditionals and attacks, in the order they were
+    /// evaluated.
+    branches: &'e [Branch],
+
+    /// Resolves the variables of the expression, if any are bound.
+    resolver: Option<&'e dyn Resolver>,
+
+    /// The index of the next unused entry of `pools`.
+    next_pool: usize,
+
+    /// The index of the next unused entry of `branches`.
+    next_branch: usize,
+
+    /// The steps recorded so far.
+    steps: Vec<Step>,
+}
 
 /// A pretty-printer for dice expressions.
-pub struct PP<'o, W: Write> {
+pub struct PP<'o, 'e, W: Write> {
     /// The writer to which the pretty-printed expression is written.
     writer: &'o mut W,
 
     /// Whether the most recent operation node is a product (in which case,
     /// terms will require parentheses).
     prod: bool,
+
+    /// The loosest construct that may be written without parentheses at the
+    /// current position.
+    precedence: Precedence,
+
+    /// The results of an evaluation with which to annotate the expression, if
+    /// the pretty-printer is in annotated mode.
+    annotation: Option<Annotation<'e>>,
 }
 
-impl<'o, W: Write> PP<'o, W> {
+impl<'e> Annotation<'e> {
+    pub fn new(
+        rolls: &'e [DieRoll],
+        pools: &'e [PoolTotal],
+        branches: &'e [Branch],
+        resolver: Option<&'e dyn Resolver>,
+    ) -> Self {
+        Self {
+            rolls,
+            pools,
+            branches,
+            resolver,
+            next_pool: 0,
+            next_branch: 0,
+            steps: Vec::new(),
+        }
+    }
+
+    fn next_pool(&mut self) -> Option<&'e PoolTotal> {
+        let pool = self.pools.get(self.next_pool)?;
+        self.next_pool += 1;
+        Some(pool)
+    }
+
+    fn next_branch(&mut self) -> Option<Branch> {
+        let branch = self.branches.get(self.next_branch)?;
+        self.next_branch += 1;
+        Some(*branch)
+    }
+}
+
+/// Explains an evaluation of the expression rooted at `node`, which produced
+/// `value`, as a step for each roll followed by a step combining their totals.
+pub fn explain(node: &Node, value: i32, annotation: Annotation) -> Result<Vec<Step>, FmtError> {
+    let mut expression = String::new();
+    let mut pp = PP::annotated(&mut expression, annotation);
+    pp.write(node)?;
+    let mut steps = pp
+        .annotation
+        .take()
+        .map(|annotation| annotation.steps)
+        .unwrap_or_default();
+
+    // A lone roll is already explained by its own step.
+    if steps.is_empty() || expression != value.to_string() {
+        steps.push(Step {
+            expression,
+            dice: Vec::new(),
+            value,
+        });
+    }
+
+    Ok(steps)
+}
+
+impl<'o, 'e, W: Write> PP<'o, 'e, W> {
     pub fn new(writer: &'o mut W) -> Self {
         Self {
             writer,
             prod: false,
+            precedence: Precedence::Conditional,
+            annotation: None,
+        }
+    }
+
+    /// Creates a pretty-printer that writes the totals of the rolls in
+    /// `annotation` in place of the rolls themselves.
+    pub fn annotated(writer: &'o mut W, annotation: Annotation<'e>) -> Self {
+        Self {
+            annotation: Some(annotation),
+            ..Self::new(writer)
         }
     }
 
@@ -31,22 +172,126 @@ impl<'o, W: Write> PP<'o, W> {
     }
 
     fn visit(&mut self, node: &Node) -> Result<(), FmtError> {
+        if self.annotation.is_some() {
+            if let Node::Roll { .. } | Node::Count { .. } = node {
+                return self.annotated_roll(node);
+            }
+        }
+
         match node {
             Node::Lit { value } => self.lit(*value),
+            Node::Var { name } => self.var(name),
             Node::Roll {
                 count,
                 sides,
+                fudge,
                 select,
-            } => self.roll(&count, &sides, select.as_ref().map(|n| n.as_ref())),
+            } => self.roll(&count, &sides, *fudge, select.as_ref().map(|n| n.as_ref())),
             Node::Select { selection, next } => {
                 self.select(selection, next.as_ref().map(|n| n.as_ref()))
             }
+            Node::Count {
+                roll,
+                success,
+                failure,
+            } => self.count(roll, success, failure.as_ref()),
+            Node::Tag { label, right } => self.tag(label, right.as_ref()),
             Node::Neg { right } => self.neg(right.as_ref()),
             Node::Add { left, right } => self.add(left.as_ref(), right.as_ref()),
             Node::Sub { left, right } => self.sub(left.as_ref(), right.as_ref()),
             Node::Mul { left, right } => self.mul(left.as_ref(), right.as_ref()),
             Node::Div { left, right } => self.div(left.as_ref(), right.as_ref()),
+            Node::Compare {
+                comparison,
+                left,
+                right,
+            } => self.compare(*comparison, left.as_ref(), right.as_ref()),
+            Node::Cond {
+                condition,
+                then,
+                otherwise,
+            } => self.cond(condition.as_ref(), then.as_ref(), otherwise.as_ref()),
+            Node::Hit {
+                attack,
+                target,
+                damage,
+            } => self.hit(attack.as_ref(), target.as_ref(), damage.as_ref()),
+            Node::Call { function, args } => self.call(*function, args),
+        }
+    }
+
+    /// Writes the total of a roll (or of a count of successes) in place of the
+    /// roll and records a step listing its dice. Rolls nested within it, such
+    /// as the sides of `1d(1d6)`, are evaluated first, so they are recorded
+    /// first.
+    fn annotated_roll(&mut self, node: &Node) -> Result<(), FmtError> {
+        let roll = match node {
+            Node::Count { roll, .. } => roll.as_ref(),
+            _ => node,
+        };
+
+        if let Node::Roll { count, sides, .. } = roll {
+            let mut nested = String::new();
+            let mut pp = PP {
+                annotation: self.annotation.take(),
+                ..PP::new(&mut nested)
+            };
+            pp.visit(count)?;
+            pp.visit(sides)?;
+            self.annotation = pp.annotation.take();
         }
+
+        let mut expression = String::new();
+        PP::new(&mut expression).write(node)?;
+
+        let Some(annotation) = self.annotation.as_mut() else {
+            return write!(self.writer, "{expression}");
+        };
+        let Some(pool) = annotation.next_pool() else {
+            return write!(self.writer, "{expression}");
+        };
+
+        annotation.steps.push(Step {
+            expression,
+            dice: annotation.rolls[pool.dice.clone()].to_vec(),
+            value: pool.total,
+        });
+        write!(self.writer, "{}", pool.total)
+    }
+
+    /// Calls `f` with annotation suspended, for parts of the expression that
+    /// were not evaluated, such as a branch that was not taken.
+    fn unannotated<F>(&mut self, f: F) -> Result<(), FmtError>
+    where
+        F: FnOnce(&mut Self) -> Result<(), FmtError>,
+    {
+        let annotation = self.annotation.take();
+        let result = f(self);
+        self.annotation = annotation;
+        result
+    }
+
+    /// Visits `node` as an operand of an arithmetic operation, where
+    /// comparisons and conditionals must be parenthesized.
+    fn operand(&mut self, node: &Node) -> Result<(), FmtError> {
+        let was_precedence = self.precedence;
+        self.precedence = Precedence::Sum;
+        self.visit(node)?;
+        self.precedence = was_precedence;
+        Ok(())
+    }
+
+    /// Visits `node` in a position that is already delimited, such as an
+    /// argument of `hit`, where nothing needs to be parenthesized.
+    fn delimited(&mut self, node: &Node) -> Result<(), FmtError> {
+        let was_prod = self.prod;
+        let was_precedence = self.precedence;
+        self.prod = false;
+        self.precedence = Precedence::Conditional;
+        self.visit(node)?;
+        self.prod = was_prod;
+        self.precedence = was_precedence;
+        Ok(())
     }
 
     fn lit(&mut self, value: i32) -> Result<(), FmtError> {
@@ -54,10 +299,38 @@ impl<'o, W: Write> PP<'o, W> {
         Ok(())
     }
 
-    fn roll(&mut self, count: &Node, sides: &Node, select: Option<&Node>) -> Result<(), FmtError> {
+    fn var(&mut self, name: &str) -> Result<(), FmtError> {
+        let value = self
+            .annotation
+            .as_ref()
+            .and_then(|annotation| annotation.resolver)
+            .and_then(|resolver| resolver.resolve(name));
+
+        match value {
+            Some(value) => write!(self.writer, "{}", value)?,
+            None => write!(self.writer, "@{}", name)?,
+        }
+        Ok(())
+    }
+
+    fn roll(
+        &mut self,
+        count: &Node,
+        sides: &Node,
+        fudge: bool,
+        select: Option<&Node>,
+    ) -> Result<(), FmtError> {
         self.visit(count)?;
-        write!(self.writer, "d")?;
-        self.visit(sides)?;
+
+        if fudge {
+            write!(self.writer, "dF")?;
+        } else if let Node::Lit { value } = sides {
+            write!(self.writer, "d{value}")?;
+        } else {
+            write!(self.writer, "d(")?;
+            self.delimited(sides)?;
+            write!(self.writer, ")")?;
+        }
 
         if let Some(selection) = &select {
             self.visit(selection)?;
@@ -74,6 +347,10 @@ impl<'o, W: Write> PP<'o, W> {
             Selection::DropLowest { .. } => write!(self.writer, "dl")?,
             Selection::Advantage => write!(self.writer, "adv")?,
             Selection::Disadvantage => write!(self.writer, "dis")?,
+            Selection::Explode { .. } => write!(self.writer, "!")?,
+            Selection::CompoundExplode { .. } => write!(self.writer, "!!")?,
+            Selection::Reroll { .. } => write!(self.writer, "r")?,
+            Selection::RerollOnce { .. } => write!(self.writer, "ro")?,
         };
 
         match selection {
@@ -87,6 +364,14 @@ impl<'o, W: Write> PP<'o, W> {
                     write!(self.writer, "1")?;
                 }
             }
+            Selection::Explode { target } | Selection::CompoundExplode { target } => {
+                if let Some(target) = target {
+                    self.target(target)?;
+                }
+            }
+            Selection::Reroll { target } | Selection::RerollOnce { target } => {
+                self.target(target)?;
+            }
             _ => {}
         };
 
@@ -97,11 +382,62 @@ impl<'o, W: Write> PP<'o, W> {
         Ok(())
     }
 
+    fn count(
+        &mut self,
+        roll: &Node,
+        success: &Target,
+        failure: Option<&Target>,
+    ) -> Result<(), FmtError> {
+        self.visit(roll)?;
+        write!(self.writer, "cs")?;
+        self.target(success)?;
+
+        if let Some(failure) = failure {
+            write!(self.writer, "f")?;
+            self.target(failure)?;
+        }
+
+        Ok(())
+    }
+
+    fn target(&mut self, target: &Target) -> Result<(), FmtError> {
+        match target.comparison {
+            Comparison::Equal => write!(self.writer, "=")?,
+            Comparison::Less => write!(self.writer, "<")?,
+            Comparison::LessOrEqual => write!(self.writer, "<=")?,
+            Comparison::Greater => write!(self.writer, ">")?,
+            Comparison::GreaterOrEqual => write!(self.writer, ">=")?,
+        };
+
+        self.visit(&target.value)
+    }
+
+    fn tag(&mut self, label: &str, right: &Node) -> Result<(), FmtError> {
+        // Labels bind to the whole term before them, so a label within a
+        // product must be parenthesized to keep it on the same operand.
+        let was_prod = self.prod;
+        self.prod = true;
+
+        if was_prod {
+            write!(self.writer, "(")?;
+        }
+
+        self.operand(right)?;
+        write!(self.writer, "[{label}]")?;
+
+        if was_prod {
+            write!(self.writer, ")")?;
+        }
+
+        self.prod = was_prod;
+        Ok(())
+    }
+
     fn neg(&mut self, right: &Node) -> Result<(), FmtError> {
         let was_prod = self.prod;
         self.prod = true;
         write!(self.writer, "-")?;
-        self.visit(right)?;
+        self.operand(right)?;
         self.prod = was_prod;
         Ok(())
     }
@@ -114,9 +450,9 @@ impl<'o, W: Write> PP<'o, W> {
             write!(self.writer, "(")?;
         }
 
-        self.visit(left)?;
+        self.operand(left)?;
         write!(self.writer, " + ")?;
-        self.visit(right)?;
+        self.operand(right)?;
 
         if was_prod {
             write!(self.writer, ")")?;
@@ -134,9 +470,9 @@ impl<'o, W: Write> PP<'o, W> {
             write!(self.writer, "(")?;
         }
 
-        self.visit(left)?;
+        self.operand(left)?;
         write!(self.writer, " - ")?;
-        self.visit(right)?;
+        self.operand(right)?;
 
         if was_prod {
             write!(self.writer, ")")?;
@@ -150,9 +486,9 @@ impl<'o, W: Write> PP<'o, W> {
         let was_prod = self.prod;
         self.prod = true;
 
-        self.visit(left)?;
+        self.operand(left)?;
         write!(self.writer, " × ")?;
-        self.visit(right)?;
+        self.operand(right)?;
 
         self.prod = was_prod;
         Ok(())
@@ -162,11 +498,166 @@ impl<'o, W: Write> PP<'o, W> {
         let was_prod = self.prod;
         self.prod = true;
 
-        self.visit(left)?;
+        self.operand(left)?;
         write!(self.writer, " / ")?;
-        self.visit(right)?;
+        self.operand(right)?;
+
+        self.prod = was_prod;
+        Ok(())
+    }
+
+    fn compare(
+        &mut self,
+        comparison: Comparison,
+        left: &Node,
+        right: &Node,
+    ) -> Result<(), FmtError> {
+        let parenthesize = self.precedence == Precedence::Sum;
+        if parenthesize {
+            write!(self.writer, "(")?;
+        }
+
+        let was_prod = self.prod;
+        self.prod = false;
+
+        // A comparison directly after a roll would count successes instead,
+        // so the roll must be parenthesized to compare its total (unless it is
+        // annotated, in which case its total is written instead).
+        if ends_with_roll(left) && self.annotation.is_none() {
+            write!(self.writer, "(")?;
+            self.delimited(left)?;
+            write!(self.writer, ")")?;
+        } else {
+            self.operand(left)?;
+        }
+
+        match comparison {
+            Comparison::Equal => write!(self.writer, " = ")?,
+            Comparison::Less => write!(self.writer, " < ")?,
+            Comparison::LessOrEqual => write!(self.writer, " <= ")?,
+            Comparison::Greater => write!(self.writer, " > ")?,
+            Comparison::GreaterOrEqual => write!(self.writer, " >= ")?,
+        };
+
+        self.operand(right)?;
+        self.prod = was_prod;
+
+        if parenthesize {
+            write!(self.writer, ")")?;
+        }
+
+        Ok(())
+    }
+
+    fn cond(&mut self, condition: &Node, then: &Node, otherwise: &Node) -> Result<(), FmtError> {
+        let parenthesize = self.precedence != Precedence::Conditional;
+        if parenthesize {
+            write!(self.writer, "(")?;
+        }
+
+        let was_prod = self.prod;
+        let was_precedence = self.precedence;
+        self.prod = false;
+
+        self.precedence = Precedence::Condition;
+        self.visit(condition)?;
+        let branch = self
+            .annotation
+            .as_mut()
+            .and_then(|annotation| annotation.next_branch());
+
+        write!(self.writer, " ? ")?;
+        self.precedence = Precedence::Conditional;
+        match branch {
+            Some(Branch::Else) => self.unannotated(|pp| pp.visit(then))?,
+            _ => self.visit(then)?,
+        }
+
+        write!(self.writer, " : ")?;
+        match branch {
+            Some(Branch::Then) => self.unannotated(|pp| pp.visit(otherwise))?,
+            _ => self.visit(otherwise)?,
+        }
 
         self.prod = was_prod;
+        self.precedence = was_precedence;
+
+        if parenthesize {
+            write!(self.writer, ")")?;
+        }
+
         Ok(())
     }
+
+    fn hit(&mut self, attack: &Node, target: &Node, damage: &Node) -> Result<(), FmtError> {
+        write!(self.writer, "hit(")?;
+        self.delimited(attack)?;
+        write!(self.writer, ", ")?;
+        self.delimited(target)?;
+        let branch = self
+            .annotation
+            .as_mut()
+            .and_then(|annotation| annotation.next_branch());
+
+        write!(self.writer, ", ")?;
+        match branch {
+            Some(Branch::Miss) => self.unannotated(|pp| pp.delimited(damage))?,
+            _ => self.delimited(damage)?,
+        }
+        write!(self.writer, ")")?;
+        Ok(())
+    }
+
+    fn call(&mut self, function: Function, args: &[Node]) -> Result<(), FmtError> {
+        write!(self.writer, "{}(", function.name())?;
+
+        for (i, arg) in args.iter().enumerate() {
+            if i > 0 {
+                write!(self.writer, ", ")?;
+            }
+            self.delimited(arg)?;
+        }
+
+        write!(self.writer, ")")?;
+        Ok(())
+    }
+}
+
+/// Returns whether the printed form of `node` ends with a roll, which would
+/// absorb a following comparison as a count of successes.
+fn ends_with_roll(node: &Node) -> bool {
+    match node {
+        Node::Roll { .. } => true,
+        Node::Neg { right }
+        | Node::Add { right, .. }
+        | Node::Sub { right, .. }
+        | Node::Mul { right, .. }
+        | Node::Div { right, .. } => ends_with_roll(right),
+        _ => false,
+    }
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        if self.dice.is_empty() {
+            return write!(f, "{} = {}", self.expression, self.value);
+        }
+
+        write!(f, "{} → [", self.expression)?;
+        for (i, die) in self.dice.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+
+            // Discarded dice are struck through and exploded dice are marked.
+            let exploded = if die.exploded { "!" } else { "" };
+            if die.keep {
+                write!(f, "{}{exploded}", die.result)?;
+            } else {
+                write!(f, "~{}{exploded}~", die.result)?;
+            }
+        }
+
+        write!(f, "] = {}", self.value)
+    }
 }
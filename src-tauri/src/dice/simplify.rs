@@ -0,0 +1,356 @@
+// Copyright (c) 2025 Jonathon B. Cobb
+// Licensed under the MIT License
+
+//! This module contains a normalization pass that rewrites an AST into a
+//! simpler, canonical form with the same distribution of results. Constant
+//! subexpressions are folded, like dice are merged (`1d6 + 2d6` becomes `3d6`)
+//! and the terms of each sum are put in a canonical order: plain dice from
+//! largest to smallest, then any other terms, then subtracted terms, and
+//! finally the constant. Two spellings of the same expression, such as
+//! `d6 + 2d6 + 1` and `1 + 3d6`, therefore normalize to the same tree.
+//!
+//! Folding never hides an error: divisions by zero and operations that would
+//! overflow are left for the evaluator to report.
+
+use std::cmp::Reverse;
+
+use super::ast::{Comparison, Function, Node};
+use super::eval::{compare, divide};
+use super::pp::PP;
+
+/// A term of a sum and whether it is subtracted.
+struct Term {
+    negative: bool,
+    node: Box<Node>,
+}
+
+/// Returns the normal form of the expression rooted at `node`.
+pub fn normalize(node: &Node) -> Box<Node> {
+    let normal = match node {
+        Node::Lit { .. } | Node::Var { .. } | Node::Select { .. } => node.clone(),
+        Node::Roll {
+            count,
+            sides,
+            fudge,
+            select,
+        } => Node::Roll {
+            count: normalize(count),
+            sides: normalize(sides),
+            fudge: *fudge,
+            select: select.clone(),
+        },
+        Node::Count {
+            roll,
+            success,
+            failure,
+        } => Node::Count {
+            roll: normalize(roll),
+            success: success.clone(),
+            failure: failure.clone(),
+        },
+        Node::Tag { label, right } => Node::Tag {
+            label: label.clone(),
+            right: normalize(right),
+        },
+        Node::Neg { .. } | Node::Add { .. } | Node::Sub { .. } => return sum(node),
+        Node::Mul { left, right } => {
+            let (left, right) = (normalize(left), normalize(right));
+            match (left.as_ref(), right.as_ref()) {
+                (Node::Lit { value: l }, Node::Lit { value: r }) => match l.checked_mul(*r) {
+                    Some(value) => Node::Lit { value },
+                    None => Node::Mul { left, right },
+                },
+                _ => Node::Mul { left, right },
+            }
+        }
+        Node::Div { left, right } => {
+            let (left, right) = (normalize(left), normalize(right));
+            match (left.as_ref(), right.as_ref()) {
+                (Node::Lit { value: l }, Node::Lit { value: r }) => match l.checked_div(*r) {
+                    Some(value) => Node::Lit { value },
+                    None => Node::Div { left, right },
+                },
+                _ => Node::Div { left, right },
+            }
+        }
+        Node::Compare {
+            comparison,
+            left,
+            right,
+        } => compare_node(*comparison, normalize(left), normalize(right)),
+        Node::Cond {
+            condition,
+            then,
+            otherwise,
+        } => {
+            let condition = normalize(condition);
+            match condition.as_ref() {
+                Node::Lit { value: 0 } => return normalize(otherwise),
+                Node::Lit { .. } => return normalize(then),
+                _ => Node::Cond {
+                    condition,
+                    then: normalize(then),
+                    otherwise: normalize(otherwise),
+                },
+            }
+        }
+        Node::Hit {
+            attack,
+            target,
+            damage,
+        } => Node::Hit {
+            attack: normalize(attack),
+            target: normalize(target),
+            damage: normalize(damage),
+        },
+        Node::Call { function, args } => call(*function, args),
+    };
+
+    Box::new(normal)
+}
+
+fn compare_node(comparison: Comparison, left: Box<Node>, right: Box<Node>) -> Node {
+    match (left.as_ref(), right.as_ref()) {
+        (Node::Lit { value: l }, Node::Lit { value: r }) => Node::Lit {
+            value: compare(comparison, *l, *r) as i32,
+        },
+        _ => Node::Compare {
+            comparison,
+            left,
+            right,
+        },
+    }
+}
+
+fn call(function: Function, args: &[Node]) -> Node {
+    // A division is rounded by the function rather than truncated, so it must
+    // not be folded on its own.
+    if let (Function::Floor | Function::Ceil | Function::Round, [Node::Div { left, right }]) =
+        (function, args)
+    {
+        let (left, right) = (normalize(left), normalize(right));
+        if let (Node::Lit { value: l }, Node::Lit { value: r }) = (left.as_ref(), right.as_ref()) {
+            if let Some(value) = divide(function, *l, *r) {
+                return Node::Lit { value };
+            }
+        }
+
+        return Node::Call {
+            function,
+            args: vec![Node::Div { left, right }],
+        };
+    }
+
+    let args: Vec<Node> = args.iter().map(|arg| *normalize(arg)).collect();
+    let values: Option<Vec<i32>> = args
+        .iter()
+        .map(|arg| match arg {
+            Node::Lit { value } => Some(*value),
+            _ => None,
+        })
+        .collect();
+
+    let value = values.and_then(|values| match function {
+        Function::Min => values.into_iter().min(),
+        Function::Max => values.into_iter().max(),
+        Function::Floor | Function::Ceil | Function::Round => values.first().copied(),
+        Function::Abs => values.first().and_then(|value| value.checked_abs()),
+    });
+
+    match value {
+        Some(value) => Node::Lit { value },
+        _ => Node::Call { function, args },
+    }
+}
+
+/// Normalizes a sum by collecting its terms, folding the constant terms into
+/// one, merging like dice and sorting what remains.
+fn sum(node: &Node) -> Box<Node> {
+    let mut flat = Vec::new();
+    terms(node, false, &mut flat);
+
+    let mut constant = 0i32;
+    let mut merged: Vec<Term> = Vec::new();
+    for term in flat {
+        if let Node::Lit { value } = term.node.as_ref() {
+            let value = if term.negative {
+                value.checked_neg()
+            } else {
+                Some(*value)
+            };
+            if let Some(value) = value {
+                // A constant that would overflow the total is kept as a term
+                // of its own, and the sum carries on from the new constant, so
+                // that normalizing again gives the same form.
+                match constant.checked_add(value) {
+                    Some(total) => constant = total,
+                    None => {
+                        merged.push(Term {
+                            negative: false,
+                            node: Box::new(Node::Lit { value: constant }),
+                        });
+                        constant = value;
+                    }
+                }
+                continue;
+            }
+        }
+
+        if let Some((count, sides, fudge)) = plain_roll(&term.node) {
+            let like = merged.iter_mut().find(|other| {
+                other.negative == term.negative
+                    && plain_roll(&other.node).is_some_and(|(_, s, f)| s == sides && f == fudge)
+            });
+
+            if let Some(other) = like {
+                let total = plain_roll(&other.node)
+                    .and_then(|(other_count, _, _)| other_count.checked_add(count));
+                if let Some(total) = total {
+                    *other.node = roll(total, sides, fudge);
+                    continue;
+                }
+            }
+        }
+
+        merged.push(term);
+    }
+
+    merged.sort_by_cached_key(key);
+
+    if constant != 0 || merged.is_empty() {
+        merged.push(Term {
+            negative: false,
+            node: Box::new(Node::Lit { value: constant }),
+        });
+    }
+
+    build(merged)
+}
+
+/// Collects the terms of a sum, normalizing each of them.
+fn terms(node: &Node, negative: bool, collected: &mut Vec<Term>) {
+    match node {
+        Node::Add { left, right } => {
+            terms(left, negative, collected);
+            terms(right, negative, collected);
+        }
+        Node::Sub { left, right } => {
+            terms(left, negative, collected);
+            terms(right, !negative, collected);
+        }
+        Node::Neg { right } => terms(right, !negative, collected),
+        _ => {
+            let node = normalize(node);
+
+            // Folding may turn a term, such as a conditional, into a sum.
+            if let Node::Add { .. } | Node::Sub { .. } | Node::Neg { .. } = node.as_ref() {
+                terms(&node, negative, collected);
+            } else {
+                collected.push(Term { negative, node });
+            }
+        }
+    }
+}
+
+/// Returns the count, sides and whether the dice are Fudge dice for a roll of
+/// a constant number of constant dice without selections, which may be merged
+/// with a like roll.
+fn plain_roll(node: &Node) -> Option<(i32, i32, bool)> {
+    let Node::Roll {
+        count,
+        sides,
+        fudge,
+        select: None,
+    } = node
+    else {
+        return None;
+    };
+
+    match (count.as_ref(), sides.as_ref()) {
+        (Node::Lit { value: count }, Node::Lit { value: sides }) => Some((*count, *sides, *fudge)),
+        _ => None,
+    }
+}
+
+fn roll(count: i32, sides: i32, fudge: bool) -> Node {
+    Node::Roll {
+        count: Box::new(Node::Lit { value: count }),
+        sides: Box::new(Node::Lit { value: sides }),
+        fudge,
+        select: None,
+    }
+}
+
+/// Orders the terms of a sum: added before subtracted, then plain dice from
+/// largest to smallest, then other terms by their printed form.
+fn key(term: &Term) -> (bool, u8, Reverse<i32>, bool, String) {
+    let mut printed = String::new();
+    // Writing to a string cannot fail.
+    let _ = PP::new(&mut printed).write(&term.node);
+
+    match plain_roll(&term.node) {
+        Some((_, sides, fudge)) => (term.negative, 0, Reverse(sides), fudge, printed),
+        None => (term.negative, 1, Reverse(0), false, printed),
+    }
+}
+
+/// Builds a sum from its sorted terms, subtracting negative constants rather
+/// than adding them.
+fn build(terms: Vec<Term>) -> Box<Node> {
+    let mut result: Option<Box<Node>> = None;
+
+    for Term { negative, node } in terms {
+        let (negative, node) = match node.as_ref() {
+            Node::Lit { value } if *value < 0 && !negative => match value.checked_neg() {
+                Some(value) => (true, Box::new(Node::Lit { value })),
+                None => (negative, node),
+            },
+            _ => (negative, node),
+        };
+
+        result = Some(match (result, negative) {
+            (None, false) => node,
+            (None, true) => match node.as_ref() {
+                Node::Lit { value } if *value != i32::MIN => Box::new(Node::Lit { value: -value }),
+                _ => Box::new(Node::Neg { right: node }),
+            },
+            (Some(left), false) => Box::new(Node::Add { left, right: node }),
+            (Some(left), true) => Box::new(Node::Sub { left, right: node }),
+        });
+    }
+
+    result.unwrap_or_else(|| Box::new(Node::Lit { value: 0 }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dice::DiceExpr;
+
+    #[test]
+    fn test_normalize() {
+        let canonical = |input: &str| DiceExpr::parse(input).unwrap().normalize().to_string();
+
+        assert_eq!(canonical("1d6 + 2d6"), "3d6");
+        assert_eq!(canonical("2 + d8 + 3 + 1d8"), "2d8 + 5");
+        assert_eq!(canonical("3 - 1d4 + 2d6 - d4"), "2d6 - 2d4 + 3");
+        assert_eq!(canonical("1d6 + 1d8 + 1d20 - 2"), "1d20 + 1d8 + 1d6 - 2");
+        assert_eq!(canonical("1d6 - 1d6"), "1d6 - 1d6");
+        assert_eq!(canonical("-(1d6 + 2)"), "-1d6 - 2");
+        assert_eq!(canonical("4d6kh3 + 1d6"), "1d6 + 4d6kh3");
+        assert_eq!(canonical("2d(2*3) + 0"), "2d6");
+        assert_eq!(canonical("floor(7 / 2) + max(1, 3) + ceil(7 / 2)"), "10");
+        assert_eq!(canonical("1 > 0 ? 1d6 + 1d6 : 1d4"), "2d6");
+        assert_eq!(canonical("1d6 / 0"), "1d6 / 0");
+        assert_eq!(canonical("2147483647 + 1"), "2147483647 + 1");
+        assert_eq!(canonical("2d8[fire] + 1d8"), "1d8 + 2d8[fire]");
+
+        for input in [
+            "2147483647 + 1",
+            "1 + 2147483647 + 1",
+            "-2147483647 - 2 + 3d6",
+        ] {
+            let once = DiceExpr::parse(input).unwrap().normalize();
+            assert_eq!(once.normalize().to_string(), once.to_string(), "{input}");
+        }
+    }
+}
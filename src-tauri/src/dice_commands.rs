@@ -1,13 +1,437 @@
 // Copyright (c) 2025 Jonathon B. Cobb
 // Licensed under the MIT License
 
-use crate::dice::{DiceExpr, Roll};
+use std::collections::HashMap;
 
+use chrono::Utc;
+use serde::Serialize;
+use tauri::{async_runtime::spawn_blocking, AppHandle, Emitter, State as TauriState};
+use ts_rs::TS;
+
+use crate::config::CriticalHits;
+use crate::db::{RollLogData, RollLogFilter, RollLogRecord};
+use crate::dice::{
+    ast::Comparison,
+    eval::{Critical, Resolver},
+    lexer::{self, SpannedKind},
+    markup::Notes,
+    parser, random_seed, DiceBatch, DiceExpr, Distribution, Error, Roll,
+};
+use crate::game::ParticipantId;
+use crate::state::{AppState, AppStateMutex};
+
+/// The odds of a roll meeting or beating a target such as an armor class or a
+/// saving throw DC.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "camelCase")]
+pub struct Chance {
+    /// The probability that the roll meets or beats the target.
+    pub probability: f64,
+
+    /// The expected damage of the attack, weighted by the probability of
+    /// hitting, if a damage expression was given.
+    pub expected_damage: Option<f64>,
+
+    /// Whether the results are exact or were estimated by sampling.
+    pub exact: bool,
+}
+
+/// The tokens of a dice expression for syntax highlighting, and the first
+/// syntax error in it, if any.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "camelCase")]
+pub struct DiceCheck {
+    /// The tokens of the expression, including any invalid input.
+    pub tokens: Vec<SpannedKind>,
+
+    /// The first syntax error in the expression, if it is invalid.
+    pub error: Option<parser::Error>,
+}
+
+/// Rolls an expression, or a batch of expressions such as `6x(4d6kh3)`, and
+/// records each roll in the roll log, optionally with a label describing the
+/// roll and the name of whoever made it.
+#[tauri::command]
+pub async fn roll(
+    app: AppHandle,
+    state: TauriState<'_, AppStateMutex>,
+    expr: String,
+    label: Option<String>,
+    roller: Option<String>,
+) -> Result<Vec<Roll>, String> {
+    let batch = DiceBatch::parse(&expr).map_err(|e| e.to_string())?;
+
+    let mut state = state.lock().await;
+    let rule = critical_rule(&state);
+    let (seed, stream) = next_streams(&mut state, &batch);
+    let rolls = batch
+        .roll_seeded(seed, stream, critical(rule), false, None)
+        .map_err(|e| e.to_string())?;
+
+    if let Err(error) = record(&mut state, &batch, &rolls, false, label, roller, None).await {
+        app.emit("roll-log-error", error)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(rolls)
+}
+
+/// Rolls an expression or batch on behalf of a participant, resolving
+/// variables such as `@dex` and `@pb` against the participant's statistics.
+#[tauri::command]
+pub async fn roll_for(
+    app: AppHandle,
+    state: TauriState<'_, AppStateMutex>,
+    participant: ParticipantId,
+    expr: String,
+    label: Option<String>,
+) -> Result<Vec<Roll>, String> {
+    let batch = DiceBatch::parse(&expr).map_err(|e| e.to_string())?;
+
+    let mut state = state.lock().await;
+    let rule = critical_rule(&state);
+    let Some(game) = state.encounter.undo_stack.last_mut() else {
+        return Err("No game found".to_string());
+    };
+    let (seed, stream) = game.next_streams(batch.exprs().len() as u64);
+    let Some(roller) = game.participants.get(&participant) else {
+        return Err(format!("No participant found with id {participant}"));
+    };
+    let Some(bindings) = roller.bindings() else {
+        return Err(format!("{} has no statistics to roll with", roller.name()));
+    };
+
+    let rolls = batch
+        .roll_seeded(seed, stream, critical(rule), false, Some(&bindings))
+        .map_err(|e| e.to_string())?;
+    let roller = roller.name().to_string();
+
+    if let Err(error) = record(
+        &mut state,
+        &batch,
+        &rolls,
+        false,
+        label,
+        Some(roller),
+        Some(participant),
+    )
+    .await
+    {
+        app.emit("roll-log-error", error)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(rolls)
+}
+
+/// Returns a participant's notes with the location of each inline roll, such
+/// as `[[d20 + 5|Bite]]`, so that the rolls can be shown as buttons.
+#[tauri::command]
+pub async fn get_notes(
+    state: TauriState<'_, AppStateMutex>,
+    participant: ParticipantId,
+) -> Result<Notes, String> {
+    let state = state.lock().await;
+    let Some(game) = state.encounter.undo_stack.last() else {
+        return Err("No game found".to_string());
+    };
+    let Some(participant) = game.participants.get(&participant) else {
+        return Err(format!("No participant found with id {participant}"));
+    };
+
+    Ok(Notes::parse(participant.notes()).into_utf16())
+}
+
+/// Rolls the inline roll at `index` in a participant's notes on their behalf,
+/// labelled with the roll's label, and records it in the roll log.
+#[tauri::command]
+pub async fn roll_note(
+    app: AppHandle,
+    state: TauriState<'_, AppStateMutex>,
+    participant: ParticipantId,
+    index: usize,
+) -> Result<Vec<Roll>, String> {
+    let mut state = state.lock().await;
+    let rule = critical_rule(&state);
+    let Some(game) = state.encounter.undo_stack.last_mut() else {
+        return Err("No game found".to_string());
+    };
+    let Some(roller) = game.participants.get(&participant) else {
+        return Err(format!("No participant found with id {participant}"));
+    };
+
+    let notes = Notes::parse(roller.notes());
+    let Some(inline) = notes.rolls.get(index) else {
+        return Err(format!(
+            "No inline roll {index} in the notes of {}",
+            roller.name()
+        ));
+    };
+    if let Some(error) = &inline.error {
+        return Err(error.message.clone());
+    }
+
+    let batch = inline.batch().map_err(|e| e.to_string())?;
+    let label = inline.label.clone();
+    let roller = roller.name().to_string();
+
+    let (seed, stream) = game.next_streams(batch.exprs().len() as u64);
+    let bindings = game
+        .participants
+        .get(&participant)
+        .and_then(|participant| participant.bindings());
+    let rolls = batch
+        .roll_seeded(
+            seed,
+            stream,
+            critical(rule),
+            false,
+            bindings.as_ref().map(|b| b as &dyn Resolver),
+        )
+        .map_err(|e| e.to_string())?;
+
+    if let Err(error) = record(
+        &mut state,
+        &batch,
+        &rolls,
+        false,
+        label,
+        Some(roller),
+        Some(participant),
+    )
+    .await
+    {
+        app.emit("roll-log-error", error)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(rolls)
+}
+
+/// Rolls an expression or batch as a critical hit, using the open campaign's
+/// rule for critical hits.
+#[tauri::command]
+pub async fn roll_critical(
+    app: AppHandle,
+    state: TauriState<'_, AppStateMutex>,
+    expr: String,
+    label: Option<String>,
+    roller: Option<String>,
+) -> Result<Vec<Roll>, String> {
+    let batch = DiceBatch::parse(&expr).map_err(|e| e.to_string())?;
+
+    let mut state = state.lock().await;
+    let rule = critical_rule(&state);
+    let (seed, stream) = next_streams(&mut state, &batch);
+    let rolls = batch
+        .roll_seeded(seed, stream, critical(rule), true, None)
+        .map_err(|e| e.to_string())?;
+
+    if let Err(error) = record(&mut state, &batch, &rolls, true, label, roller, None).await {
+        app.emit("roll-log-error", error)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(rolls)
+}
+
+/// Replays a roll from the roll log without recording it again. The roll is
+/// made with the seed and stream it was made with, as a critical hit if it was
+/// one, under the rule for critical hits in effect at the time and with the
+/// values its variables had, so that it repeats exactly.
+#[tauri::command]
+pub async fn replay_roll(state: TauriState<'_, AppStateMutex>, id: i64) -> Result<Roll, String> {
+    let state = state.lock().await;
+    let record = match &state.campaign {
+        Some(campaign) => campaign.db.get_roll(id).await.map_err(|e| e.to_string())?,
+        None => match state.roll_log.get(id) {
+            Some(record) => record.clone(),
+            None => return Err(format!("No roll found with id {id}")),
+        },
+    };
+
+    let variables: HashMap<String, i32> = record.variables.into_iter().collect();
+    DiceExpr::parse(&record.expression)
+        .and_then(|expr| {
+            expr.roll_seeded(
+                record.seed as u64,
+                record.stream as u64,
+                critical(record.critical_rule),
+                record.critical,
+                Some(&variables),
+            )
+        })
+        .map_err(|e| e.to_string())
+}
+
+/// Lists a page of the roll log, from newest to oldest, optionally filtered by
+/// roller, label or expression.
 #[tauri::command]
-pub fn roll(expr: String) -> Result<Roll, String> {
+pub async fn get_roll_log(
+    state: TauriState<'_, AppStateMutex>,
+    filter: Option<RollLogFilter>,
+    offset: u32,
+    limit: u32,
+) -> Result<Vec<RollLogRecord>, String> {
+    let filter = filter.unwrap_or_default();
+    let state = state.lock().await;
+    match &state.campaign {
+        Some(campaign) => campaign
+            .db
+            .list_rolls(&filter, offset, limit)
+            .await
+            .map_err(|e| e.to_string()),
+        None => Ok(state.roll_log.list(&filter, offset, limit)),
+    }
+}
+
+/// Clears the roll log.
+#[tauri::command]
+pub async fn clear_roll_log(state: TauriState<'_, AppStateMutex>) -> Result<(), String> {
+    let mut state = state.lock().await;
+    match &state.campaign {
+        Some(campaign) => campaign.db.clear_rolls().await.map_err(|e| e.to_string()),
+        None => {
+            state.roll_log.clear();
+            Ok(())
+        }
+    }
+}
+
+/// Returns the open campaign's rule for critical hits, which applies both to
+/// rolls made as critical hits and to natural 20s rolled by `hit`.
+fn critical_rule(state: &AppState) -> CriticalHits {
+    state
+        .campaign
+        .as_ref()
+        .map(|c| c.settings.rules.critical_hits)
+        .unwrap_or_default()
+}
+
+/// Returns how the dice of a critical hit are rolled under `rule`.
+fn critical(rule: CriticalHits) -> Critical {
+    match rule {
+        CriticalHits::MaxPlusRoll => Critical::MaxPlusRoll,
+        CriticalHits::DoubleDice => Critical::DoubleDice,
+    }
+}
+
+/// Takes the next seed and streams for a batch from the current game so that
+/// its rolls can be replayed, or draws a fresh seed if there is no game.
+fn next_streams(state: &mut AppState, batch: &DiceBatch) -> (u64, u64) {
+    match state.encounter.undo_stack.last_mut() {
+        Some(game) => game.next_streams(batch.exprs().len() as u64),
+        None => (random_seed(&mut rand::thread_rng()), 0),
+    }
+}
+
+/// Records each roll of a batch in the open campaign's roll log, or in memory
+/// if no campaign is open. Each roll is recorded with its own expression so
+/// that it can be replayed on its own, along with whether it was `forced` to
+/// be a critical hit and the participant it was made for, if any. A roll that
+/// cannot be recorded is still made, so the rest of the batch is recorded and
+/// the first database error is returned for the caller to report with a
+/// `roll-log-error` event.
+async fn record(
+    state: &mut AppState,
+    batch: &DiceBatch,
+    rolls: &[Roll],
+    forced: bool,
+    label: Option<String>,
+    roller: Option<String>,
+    participant: Option<ParticipantId>,
+) -> Result<(), String> {
+    let timestamp = Utc::now().to_rfc3339();
+    let critical_rule = critical_rule(state);
+    let mut failure = None;
+
+    for (expr, roll) in batch.exprs().iter().zip(rolls) {
+        let data = RollLogData {
+            timestamp: timestamp.clone(),
+            expression: format!("{expr:#}"),
+            value: roll.value,
+            dice: roll.dice.clone(),
+            explanation: roll.explanation.clone(),
+            seed: roll.seed as i64,
+            stream: roll.stream as i64,
+            label: label.clone(),
+            roller: roller.clone(),
+            critical: forced,
+            critical_rule,
+            participant: participant.map(u32::from),
+            variables: roll.variables.clone(),
+        };
+
+        match &state.campaign {
+            Some(campaign) => {
+                if let Err(e) = campaign.db.insert_roll(&data).await {
+                    failure.get_or_insert(format!(
+                        "Failed to record roll of {}: {e}",
+                        data.expression
+                    ));
+                }
+            }
+            None => {
+                state.roll_log.insert(data);
+            }
+        }
+    }
+
+    failure.map_or(Ok(()), Err)
+}
+
+/// Checks the syntax of an expression or batch as it is typed, returning its
+/// tokens for highlighting and the location of any error for underlining.
+#[tauri::command]
+pub fn check_dice(expr: String) -> DiceCheck {
+    let tokens = lexer::highlight(&expr)
+        .into_iter()
+        .map(|token| SpannedKind {
+            span: token.span.to_utf16(&expr),
+            ..token
+        })
+        .collect();
+    let error = parser::parse_batch(&expr).err().map(|error| parser::Error {
+        span: error.span.to_utf16(&expr),
+        ..error
+    });
+
+    DiceCheck { tokens, error }
+}
+
+/// Computes the distribution of an expression's results. An expression too
+/// complex to analyze exactly is sampled off the async runtime.
+#[tauri::command]
+pub async fn distribution(expr: String) -> Result<Distribution, String> {
     let dice_expr = DiceExpr::parse(&expr).map_err(|e| e.to_string())?;
-    let result = dice_expr
-        .roll(&mut rand::thread_rng())
+    spawn_blocking(move || dice_expr.distribution())
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Computes the chance that an expression meets `target`, and the expected
+/// damage if `damage` is given, off the async runtime as for
+/// [`distribution`].
+#[tauri::command]
+pub async fn chance(expr: String, target: i32, damage: Option<String>) -> Result<Chance, String> {
+    let roll = DiceExpr::parse(&expr).map_err(|e| e.to_string())?;
+    let damage = damage
+        .map(|damage| DiceExpr::parse(&damage))
+        .transpose()
         .map_err(|e| e.to_string())?;
-    Ok(result)
+
+    let (roll, damage) = spawn_blocking(move || {
+        let roll = roll.distribution()?;
+        let damage = damage.map(|damage| damage.distribution()).transpose()?;
+        Ok::<_, Error>((roll, damage))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())?;
+    let probability = roll.probability(Comparison::GreaterOrEqual, target);
+
+    Ok(Chance {
+        probability,
+        expected_damage: damage.as_ref().map(|damage| probability * damage.mean()),
+        exact: roll.exact && damage.is_none_or(|damage| damage.exact),
+    })
 }
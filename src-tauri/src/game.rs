@@ -2,8 +2,10 @@
 // Licensed under the MIT License
 
 mod common;
+mod concentration;
 mod conditions;
 mod damage;
+mod death_saves;
 mod expiry;
 mod game;
 mod lair;
@@ -13,10 +15,12 @@ mod player;
 pub mod time;
 
 pub use common::*;
+pub use concentration::*;
 pub use conditions::*;
 pub use damage::*;
+pub use death_saves::*;
 pub use expiry::*;
-pub use game::{Game, ParticipantId};
+pub use game::{Game, ParticipantId, TurnChange};
 pub use lair::*;
 pub use monster::*;
 pub use participant::{Action, Participant};
@@ -5,7 +5,7 @@ use rand::Rng;
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
-use crate::dice::{roll, DiceExpr};
+use crate::dice::{eval::Resolver, roll, DiceBatch, DiceExpr};
 
 /// Represents a set of character ability scores.
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
@@ -43,15 +43,16 @@ pub struct Class {
 
 impl Stats {
     pub fn roll<TRng: Rng>(rng: &mut TRng) -> Self {
-        let dice = DiceExpr::parse("4d6kh3").unwrap();
+        let rolls = DiceBatch::parse("6x(4d6kh3)").unwrap().roll(rng).unwrap();
+        let [str, dex, con, int, wis, cha] = [0, 1, 2, 3, 4, 5].map(|i| rolls[i].value as u32);
 
         Stats {
-            str: dice.roll(rng).unwrap().value as u32,
-            dex: dice.roll(rng).unwrap().value as u32,
-            con: dice.roll(rng).unwrap().value as u32,
-            int: dice.roll(rng).unwrap().value as u32,
-            wis: dice.roll(rng).unwrap().value as u32,
-            cha: dice.roll(rng).unwrap().value as u32,
+            str,
+            dex,
+            con,
+            int,
+            wis,
+            cha,
         }
     }
 }
@@ -59,3 +60,36 @@ impl Stats {
 pub fn modifier(score: u32) -> i32 {
     (score as i32 - 10) / 2
 }
+
+/// Returns the proficiency bonus of a character with the given total level or
+/// challenge rating (CR below 1 counts as level 1).
+pub fn proficiency_bonus(level: u32) -> i32 {
+    2 + (level.max(1) as i32 - 1) / 4
+}
+
+/// The variables available to dice expressions rolled on behalf of a creature:
+/// the ability modifiers `@str`, `@dex`, `@con`, `@int`, `@wis`, and `@cha`,
+/// and the proficiency bonus `@pb`.
+#[derive(Debug, Clone, Copy)]
+pub struct Bindings<'a> {
+    /// The creature's ability scores.
+    pub stats: &'a Stats,
+
+    /// The creature's proficiency bonus.
+    pub proficiency_bonus: i32,
+}
+
+impl Resolver for Bindings<'_> {
+    fn resolve(&self, name: &str) -> Option<i32> {
+        match name {
+            "str" => Some(modifier(self.stats.str)),
+            "dex" => Some(modifier(self.stats.dex)),
+            "con" => Some(modifier(self.stats.con)),
+            "int" => Some(modifier(self.stats.int)),
+            "wis" => Some(modifier(self.stats.wis)),
+            "cha" => Some(modifier(self.stats.cha)),
+            "pb" => Some(self.proficiency_bonus),
+            _ => None,
+        }
+    }
+}
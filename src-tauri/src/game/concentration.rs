@@ -0,0 +1,163 @@
+// Copyright (c) 2025 Jonathon B. Cobb
+// Licensed under the MIT License
+
+use serde::Serialize;
+use ts_rs::TS;
+
+use crate::dice::Roll;
+
+use super::{ExpiredCondition, ParticipantId};
+
+/// The Constitution saving throw a concentrating participant must make on taking damage.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "camelCase")]
+pub struct ConcentrationCheck {
+    /// The participant who took the damage.
+    pub participant: ParticipantId,
+
+    /// The DC of the saving throw.
+    pub dc: u32,
+
+    /// The saving throw, if it was rolled automatically. A participant left incapacitated by the damage makes no save.
+    pub roll: Option<Roll>,
+
+    /// Whether concentration was broken, by a failed save or by the participant being incapacitated. A save that was
+    /// not rolled leaves concentration to be broken by hand.
+    pub broken: bool,
+
+    /// The conditions that ended with the participant's concentration.
+    pub ended: Vec<ExpiredCondition>,
+}
+
+/// Returns the DC of the concentration check for taking `damage`: 10 or half the damage, whichever is higher. If
+/// `capped`, as under the 2024 rules, the DC is at most 30.
+pub fn concentration_dc(damage: u32, capped: bool) -> u32 {
+    let dc = (damage / 2).max(10);
+    if capped {
+        dc.min(30)
+    } else {
+        dc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{conditions, time::Time, Condition, Damage, Game, Participant, Player};
+
+    fn has_condition(game: &Game, id: ParticipantId, name: &str) -> bool {
+        game.participants
+            .get(&id)
+            .is_some_and(|participant| participant.has_condition(name))
+    }
+
+    /// Spawns a caster concentrating on a spell that paralyzed a target, who was knocked prone earlier and marked by
+    /// the caster as the spell was cast.
+    fn setup() -> (Game, ParticipantId, ParticipantId) {
+        let mut game = Game::new();
+        game.spawn(Player::test("Alice", 20, 100).into());
+        game.spawn(Player::test("Bob", 10, 100).into());
+        game.begin_play();
+
+        let (alice, bob) = (game.order[0], game.order[1]);
+        let cast = Time::new(1, 20);
+        if let Some(Participant::Player(caster)) = game.participants.get_mut(&alice) {
+            caster.conditions.push(Condition::concentrating(cast));
+        }
+        if let Some(Participant::Player(target)) = game.participants.get_mut(&bob) {
+            target
+                .conditions
+                .push(Condition::prone(Time::new(0, 20)).with_instigator(alice));
+            target
+                .conditions
+                .push(Condition::marked(cast).with_instigator(alice));
+            conditions::add_condition(
+                &mut target.conditions,
+                Condition::paralyzed(cast)
+                    .with_instigator(alice)
+                    .with_concentration(),
+            );
+        }
+
+        (game, alice, bob)
+    }
+
+    #[test]
+    fn test_concentration_dc() {
+        assert_eq!(concentration_dc(7, true), 10);
+        assert_eq!(concentration_dc(25, true), 12);
+        assert_eq!(concentration_dc(80, true), 30);
+        assert_eq!(concentration_dc(80, false), 40);
+    }
+
+    #[test]
+    fn test_failed_concentration_check() {
+        let (mut game, alice, bob) = setup();
+
+        // A DC of 30 cannot be made with a d20 and no modifier.
+        let check = game
+            .damage(alice, Damage::Damage { amount: 90 }, false, true, true)
+            .unwrap()
+            .unwrap();
+        assert_eq!(check.dc, 30);
+        assert!(check.roll.is_some());
+        assert!(check.broken);
+
+        let ended: Vec<_> = check
+            .ended
+            .iter()
+            .map(|ended| (ended.participant, ended.condition.name.as_str()))
+            .collect();
+        assert_eq!(
+            ended,
+            [
+                (alice, conditions::CONCENTRATING),
+                (bob, conditions::PARALYZED),
+                (bob, conditions::INCAPACITATED),
+            ]
+        );
+        assert!(has_condition(&game, bob, conditions::PRONE));
+        assert!(has_condition(&game, bob, conditions::MARKED));
+    }
+
+    #[test]
+    fn test_unrolled_concentration_check() {
+        let (mut game, alice, bob) = setup();
+
+        let check = game
+            .damage(alice, Damage::HalfDamage { amount: 40 }, false, true, false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(check.dc, 10);
+        assert!(check.roll.is_none());
+        assert!(!check.broken);
+        assert!(has_condition(&game, bob, conditions::PARALYZED));
+
+        let ended = game.break_concentration(alice);
+        assert_eq!(ended.len(), 3);
+        assert!(!has_condition(&game, alice, conditions::CONCENTRATING));
+        assert!(!has_condition(&game, bob, conditions::PARALYZED));
+
+        // Damage without concentration calls for no check.
+        assert!(game
+            .damage(alice, Damage::Damage { amount: 5 }, false, true, true)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_incapacitation_breaks_concentration() {
+        let (mut game, alice, bob) = setup();
+
+        let check = game
+            .damage(alice, Damage::Damage { amount: 100 }, false, true, true)
+            .unwrap()
+            .unwrap();
+        assert_eq!(check.dc, 30);
+        assert!(check.roll.is_none());
+        assert!(check.broken);
+        assert!(has_condition(&game, alice, conditions::UNCONSCIOUS));
+        assert!(!has_condition(&game, bob, conditions::PARALYZED));
+    }
+}
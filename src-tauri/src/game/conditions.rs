@@ -30,6 +30,16 @@ pub struct Condition {
     /// The participant who caused the condition, if it was not self-inflicted.
     #[serde(default)]
     pub instigator: Option<ParticipantId>,
+
+    /// The names of the conditions that imply this one, if it was applied as an implication of them rather than
+    /// directly. An implied condition is removed along with the last of the conditions that imply it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub implied_by: Vec<String>,
+
+    /// Whether the condition lasts only as long as its instigator concentrates, such as the effect of a concentration
+    /// spell. It ends when the instigator's concentration is broken.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub concentration: bool,
 }
 
 pub const BLINDED: &'static str = "blinded";
@@ -59,6 +69,8 @@ impl Condition {
             start_time: start_time,
             expiry: Expiry::None,
             instigator: None,
+            implied_by: vec![],
+            concentration: false,
         }
     }
 
@@ -72,6 +84,12 @@ impl Condition {
         self
     }
 
+    /// Marks the condition as sustained by its instigator's concentration.
+    pub fn with_concentration(mut self) -> Self {
+        self.concentration = true;
+        self
+    }
+
     pub fn blinded(start_time: Time) -> Self {
         Self::new(BLINDED.into(), start_time)
     }
@@ -148,14 +166,30 @@ impl Condition {
         Self::new(UNCONSCIOUS.into(), start_time)
     }
 
-    pub fn expired_on_turn_start(&self, game_time: Time) -> bool {
+    /// Returns whether the condition expires as a turn starts at `game_time`. `instigators_turn` is whether the turn
+    /// belongs to the condition's instigator (or to its bearer, if it has no instigator).
+    pub fn expires_on_turn_start(&self, instigators_turn: bool, game_time: Time) -> bool {
+        match self.expiry {
+            Expiry::NextTurnStart => instigators_turn,
+            Expiry::Duration(duration) => game_time.has_reached(self.start_time + duration),
+            _ => false,
+        }
+    }
+
+    /// Returns whether the condition expires as a turn that started at `game_time` ends. A condition that lasts until
+    /// the end of its instigator's next turn does not expire at the end of the turn in which it was applied.
+    pub fn expires_on_turn_end(&self, instigators_turn: bool, game_time: Time) -> bool {
         match self.expiry {
-            Expiry::NextTurnStart => true,
-            Expiry::Duration(duration) => game_time >= self.start_time + duration,
+            Expiry::NextTurnEnd => instigators_turn && self.start_time != game_time,
             _ => false,
         }
     }
 
+    /// Returns whether the condition was applied only as an implication of other conditions.
+    pub fn is_implied(&self) -> bool {
+        !self.implied_by.is_empty()
+    }
+
     pub fn implications(&self) -> Vec<Condition> {
         match self.name.as_str() {
             GRAPPLED => vec![Self::restrained(self.start_time)],
@@ -174,6 +208,76 @@ impl Condition {
     }
 }
 
+/// Adds `condition` to `conditions` along with the conditions it implies, and the conditions they imply in turn, each
+/// linked to the condition that implies it. An implied condition that is already present is not added again; if it
+/// was itself implied, it is linked to its new parent as well.
+pub fn add_condition(conditions: &mut Vec<Condition>, condition: Condition) {
+    let parent = condition.name.clone();
+    let instigator = condition.instigator;
+    let implications = condition.implications();
+    conditions.push(condition);
+
+    for mut implied in implications {
+        if let Some(existing) = conditions.iter_mut().find(|c| c.name == implied.name) {
+            if existing.is_implied() && !existing.implied_by.contains(&parent) {
+                existing.implied_by.push(parent.clone());
+            }
+            continue;
+        }
+
+        implied.instigator = instigator;
+        implied.implied_by.push(parent.clone());
+        add_condition(conditions, implied);
+    }
+}
+
+/// Removes each of `removed` from `conditions`, along with any implied conditions that nothing else still implies,
+/// returning every condition that was removed.
+pub fn remove_conditions(conditions: &mut Vec<Condition>, removed: &[Condition]) -> Vec<Condition> {
+    let (mut gone, kept): (Vec<_>, Vec<_>) =
+        conditions.drain(..).partition(|c| removed.contains(c));
+    *conditions = kept;
+    gone.extend(remove_orphaned_implications(conditions));
+    gone
+}
+
+/// Removes the implied conditions that are no longer implied by any remaining condition, returning them.
+pub fn remove_orphaned_implications(conditions: &mut Vec<Condition>) -> Vec<Condition> {
+    let mut removed = Vec::new();
+
+    loop {
+        let names: Vec<String> = conditions.iter().map(|c| c.name.clone()).collect();
+        let (orphans, kept): (Vec<_>, Vec<_>) = conditions.drain(..).partition(|c| {
+            c.is_implied() && !c.implied_by.iter().any(|parent| names.contains(parent))
+        });
+        *conditions = kept;
+
+        if orphans.is_empty() {
+            break;
+        }
+        removed.extend(orphans);
+    }
+
+    let names: Vec<String> = conditions.iter().map(|c| c.name.clone()).collect();
+    for condition in conditions.iter_mut() {
+        condition.implied_by.retain(|parent| names.contains(parent));
+    }
+
+    removed
+}
+
+/// A condition that has expired and the participant it was removed from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpiredCondition {
+    /// The participant who had the condition.
+    pub participant: ParticipantId,
+
+    /// The condition that expired.
+    pub condition: Condition,
+}
+
 impl Display for Condition {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         write!(f, "{}", self.name)
@@ -185,3 +289,46 @@ impl From<Duration> for Expiry {
         Expiry::Duration(duration)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(conditions: &[Condition]) -> Vec<&str> {
+        conditions.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_implications_are_removed_with_their_parents() {
+        let time = Time::new(1, 10);
+        let mut conditions = vec![Condition::prone(time)];
+
+        add_condition(&mut conditions, Condition::petrified(time));
+        add_condition(&mut conditions, Condition::stunned(time));
+        assert_eq!(
+            names(&conditions),
+            [
+                "prone",
+                "petrified",
+                "incapacitated",
+                "unconscious",
+                "stunned"
+            ]
+        );
+        assert_eq!(
+            conditions[2].implied_by,
+            ["petrified", "unconscious", "stunned"]
+        );
+        assert!(!conditions[0].is_implied());
+
+        // Stunned still implies incapacitated, and prone was added directly.
+        let removed = remove_conditions(&mut conditions, &[Condition::petrified(time)]);
+        assert_eq!(names(&removed), ["petrified", "unconscious"]);
+        assert_eq!(names(&conditions), ["prone", "incapacitated", "stunned"]);
+        assert_eq!(conditions[1].implied_by, ["stunned"]);
+
+        let removed = remove_conditions(&mut conditions, &[Condition::stunned(time)]);
+        assert_eq!(names(&removed), ["stunned", "incapacitated"]);
+        assert_eq!(names(&conditions), ["prone"]);
+    }
+}
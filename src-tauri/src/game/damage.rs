@@ -22,6 +22,18 @@ pub enum Damage {
     Kill,
 }
 
+impl Damage {
+    /// Returns the damage dealt, after halving or doubling, or `None` if the damage kills outright.
+    pub fn amount(self) -> Option<u32> {
+        match self {
+            Damage::Damage { amount } => Some(amount),
+            Damage::HalfDamage { amount } => Some(amount / 2),
+            Damage::DoubleDamage { amount } => Some(amount * 2),
+            Damage::Kill => None,
+        }
+    }
+}
+
 /// Represents healing that can be applied to a participant.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
 #[ts(export)]
@@ -35,4 +47,7 @@ pub enum Healing {
 
     /// Set temporary HP to `amount`.
     SetTempHp { amount: u32 },
+
+    /// Set maximum HP to `amount`, reducing HP to match if it is higher.
+    SetMaxHp { amount: u32 },
 }
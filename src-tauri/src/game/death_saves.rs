@@ -0,0 +1,189 @@
+// Copyright (c) 2025 Jonathon B. Cobb
+// Licensed under the MIT License
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::dice::Roll;
+
+use super::ParticipantId;
+
+/// The death saving throws made by a player since they last dropped to 0 hit points or became stable.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "camelCase")]
+pub struct DeathSaves {
+    /// Number of successful saves.
+    pub successes: u32,
+
+    /// Number of failed saves.
+    pub failures: u32,
+
+    /// Whether the player has stabilized and no longer makes death saves.
+    pub stable: bool,
+
+    /// Whether the player was reduced to 0 hit points by damage and makes death saves until they stabilize, die or
+    /// regain hit points.
+    #[serde(default)]
+    pub dying: bool,
+}
+
+/// The outcome of a single death saving throw, or of taking damage at 0 hit points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum DeathSave {
+    /// A roll of 10 or higher.
+    Success,
+
+    /// A roll below 10, or damage taken at 0 hit points.
+    Failure,
+
+    /// A natural 20, which restores 1 hit point.
+    CriticalSuccess,
+
+    /// A natural 1, or a critical hit taken at 0 hit points, which counts as two failures.
+    CriticalFailure,
+}
+
+/// A death saving throw rolled automatically at the start of a player's turn.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "camelCase")]
+pub struct DeathSaveRoll {
+    /// The player who made the save.
+    pub participant: ParticipantId,
+
+    /// The d20 roll.
+    pub roll: Roll,
+
+    /// The outcome of the roll.
+    pub save: DeathSave,
+
+    /// The player's saves after the roll.
+    pub saves: DeathSaves,
+}
+
+impl DeathSaves {
+    /// Three failures kill a player.
+    pub fn is_dead(&self) -> bool {
+        self.failures >= 3
+    }
+
+    /// Records the outcome of a save, other than a critical success, which restores hit points instead. Three
+    /// successes stabilize the player, resetting the count of both, and either three successes or three failures end
+    /// their dying.
+    pub fn record(&mut self, save: DeathSave) {
+        match save {
+            DeathSave::Success => self.successes += 1,
+            DeathSave::Failure => self.failures = (self.failures + 1).min(3),
+            DeathSave::CriticalFailure => self.failures = (self.failures + 2).min(3),
+            DeathSave::CriticalSuccess => {}
+        }
+
+        if self.successes >= 3 {
+            *self = DeathSaves {
+                stable: true,
+                ..DeathSaves::default()
+            };
+        } else if self.is_dead() {
+            self.dying = false;
+        }
+    }
+}
+
+impl DeathSave {
+    /// Returns the outcome of a death saving throw with the given d20 roll.
+    pub fn from_roll(d20: i32) -> Self {
+        match d20 {
+            20.. => DeathSave::CriticalSuccess,
+            10..=19 => DeathSave::Success,
+            2..=9 => DeathSave::Failure,
+            _ => DeathSave::CriticalFailure,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{conditions, time::Time, Damage, Game, Healing, Participant, Player};
+
+    #[test]
+    fn test_death_saves() {
+        let time = Time::new(1, 10);
+        let mut alice = Player::test("Alice", 10, 10);
+
+        alice.damage(time, Damage::Damage { amount: 12 }, false);
+        assert_eq!(alice.hp, 0);
+        assert!(alice.has_condition(conditions::UNCONSCIOUS));
+        assert!(alice.has_condition(conditions::PRONE));
+        assert!(alice.is_dying());
+
+        for _ in 0..3 {
+            alice.death_save(time, DeathSave::Success);
+        }
+        assert!(alice.death_saves.stable);
+        assert!(!alice.is_dying());
+
+        // Damage ends stability, and a critical hit counts as two failures.
+        alice.damage(time, Damage::Damage { amount: 1 }, false);
+        assert_eq!(
+            (alice.death_saves.failures, alice.death_saves.stable),
+            (1, false)
+        );
+        alice.damage(time, Damage::Damage { amount: 1 }, true);
+        assert!(alice.death_saves.is_dead());
+        assert!(alice.has_condition(conditions::DEAD));
+        assert!(!alice.is_dying());
+
+        let mut bob = Player::test("Bob", 10, 10);
+        bob.damage(time, Damage::Damage { amount: 10 }, false);
+        assert!(bob.is_dying());
+        bob.death_save(time, DeathSave::CriticalFailure);
+        bob.death_save(time, DeathSave::CriticalSuccess);
+        assert_eq!(bob.hp, 1);
+        assert_eq!(bob.death_saves, DeathSaves::default());
+        assert!(!bob.has_condition(conditions::DEAD));
+        assert!(!bob.is_dying());
+
+        // Hit points set to 0 by hand do not call for death saves.
+        let mut carol = Player::test("Carol", 10, 10);
+        carol.heal(Healing::SetHp { amount: 0 });
+        assert!(!carol.is_dying());
+        carol.death_save(time, DeathSave::Failure);
+        assert_eq!(carol.death_saves, DeathSaves::default());
+    }
+
+    #[test]
+    fn test_death_saves_are_rolled_at_turn_start() {
+        let mut game = Game::new();
+        game.spawn(Player::test("Alice", 10, 1).into());
+        game.begin_play();
+
+        let id = game.order[0];
+        assert!(game.next_turn(true).death_save.is_none());
+
+        let time = game.time();
+        if let Some(Participant::Player(alice)) = game.participants.get_mut(&id) {
+            alice.damage(time, Damage::Damage { amount: 1 }, false);
+        }
+
+        assert!(game.next_turn(false).death_save.is_none());
+
+        let roll = game.next_turn(true).death_save.unwrap();
+        assert_eq!(roll.participant, id);
+        assert_eq!(roll.save, DeathSave::from_roll(roll.roll.value));
+
+        let Some(Participant::Player(alice)) = game.participants.get(&id) else {
+            panic!("Alice is not a player");
+        };
+        match roll.save {
+            DeathSave::CriticalSuccess => assert_eq!(alice.hp, 1),
+            _ => {
+                assert_eq!(alice.death_saves, roll.saves);
+                assert_ne!(roll.saves, DeathSaves::default());
+            }
+        }
+    }
+}
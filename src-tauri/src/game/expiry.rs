@@ -64,6 +64,7 @@ impl TryInto<StdDuration> for Expiry {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::game::{time::Time, Condition, ExpiredCondition, Game, Player};
 
     #[test]
     fn test_expiry_serialization() {
@@ -82,4 +83,75 @@ mod tests {
         assert_eq!(json_next_turn_end, r#"{"type":"nextTurnEnd"}"#);
         assert_eq!(json_duration, r#"{"type":"duration","rounds":5}"#);
     }
+
+    #[test]
+    fn test_conditions_expire_on_instigators_turn() {
+        let mut game = Game::new();
+        game.spawn(Player::test("Alice", 20, 10).into());
+        game.spawn(Player::test("Bob", 10, 10).into());
+        game.begin_play();
+
+        let (alice, bob) = (game.order[0], game.order[1]);
+        let start = Time::new(1, 20);
+        let add = |game: &mut Game, id, condition: Condition| {
+            if let Some(conditions) = game.participants.get_mut(&id).unwrap().conditions_mut() {
+                conditions.push(condition);
+            }
+        };
+
+        add(
+            &mut game,
+            bob,
+            Condition::restrained(start)
+                .with_expiry(Expiry::NextTurnEnd)
+                .with_instigator(alice),
+        );
+        add(
+            &mut game,
+            alice,
+            Condition::prone(start)
+                .with_expiry(Expiry::NextTurnStart)
+                .with_instigator(bob),
+        );
+        add(
+            &mut game,
+            bob,
+            Condition::poisoned(start).with_expiry(Duration::from_rounds(1).into()),
+        );
+        add(
+            &mut game,
+            alice,
+            Condition::frightened(start).with_expiry(Expiry::NextTurnStart),
+        );
+
+        let names = |expired: Vec<ExpiredCondition>| {
+            expired
+                .into_iter()
+                .map(|expired| (expired.participant, expired.condition.name))
+                .collect::<Vec<_>>()
+        };
+
+        // Bob's turn starts, ending Alice's prone condition but not the
+        // restraint Alice applied during her own turn.
+        assert_eq!(
+            names(game.next_turn(false).expired),
+            [(alice, "prone".to_string())]
+        );
+
+        // Alice's turn starts a round later.
+        assert_eq!(
+            names(game.next_turn(false).expired),
+            [
+                (alice, "frightened".to_string()),
+                (bob, "poisoned".to_string())
+            ]
+        );
+
+        // Alice's turn ends.
+        assert_eq!(
+            names(game.next_turn(false).expired),
+            [(bob, "restrained".to_string())]
+        );
+        assert!(game.next_turn(false).expired.is_empty());
+    }
 }
@@ -8,11 +8,16 @@ use std::{
 };
 
 use chrono::{DateTime, Utc};
-use rand::{rngs::StdRng, SeedableRng};
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
-use crate::game::time::Time;
+use crate::{
+    dice::{eval::Critical, random_seed, DiceExpr},
+    game::{
+        concentration_dc, conditions, remove_orphaned_implications, time::Time, ConcentrationCheck,
+        Condition, Damage, DeathSave, DeathSaveRoll, ExpiredCondition,
+    },
+};
 
 use super::Participant;
 
@@ -32,13 +37,31 @@ pub struct Game {
     pub game_started: DateTime<Utc>,
     pub turn_started: DateTime<Utc>,
 
-    #[serde(skip, default = "rand::rngs::StdRng::from_entropy")]
-    pub rng: StdRng,
+    /// The seed of the random number generator used for rolls made during the
+    /// encounter.
+    #[serde(default = "new_seed")]
+    pub seed: u64,
+
+    /// The next unused stream of the random number generator.
+    #[serde(default)]
+    pub stream: u64,
 
     #[serde(skip)]
     next_id: ParticipantId,
 }
 
+/// What happened as play passed to the next turn, so that it can be announced.
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnChange {
+    /// The conditions that expired as the previous turn ended or the next one started.
+    pub expired: Vec<ExpiredCondition>,
+
+    /// The death save rolled for the player whose turn started, if they are dying and saves are rolled.
+    pub death_save: Option<DeathSaveRoll>,
+}
+
 impl Game {
     pub fn new() -> Self {
         Self {
@@ -49,7 +72,8 @@ impl Game {
             turn: 0,
             game_started: Utc::now(),
             turn_started: Utc::now(),
-            rng: StdRng::from_entropy(),
+            seed: new_seed(),
+            stream: 0,
         }
     }
 
@@ -73,6 +97,20 @@ impl Game {
         self.turn = self.turn.min((self.order.len() as u32).saturating_sub(1));
     }
 
+    /// Returns the seed and stream to use for the next roll, advancing to the
+    /// next stream so that no two rolls share a sequence.
+    pub fn next_stream(&mut self) -> (u64, u64) {
+        self.next_streams(1)
+    }
+
+    /// Returns the seed and first stream of `count` consecutive streams for a
+    /// batch of rolls, advancing past all of them.
+    pub fn next_streams(&mut self, count: u64) -> (u64, u64) {
+        let stream = self.stream;
+        self.stream += count;
+        (self.seed, stream)
+    }
+
     pub fn begin_play(&mut self) {
         self.turn = 0;
         self.round = 1;
@@ -80,15 +118,25 @@ impl Game {
         self.turn_started = Utc::now();
     }
 
-    pub fn next_turn(&mut self) {
+    /// Advances to the next participant's turn, returning the conditions that expired as the current turn ended or
+    /// the next one started. If `roll_death_saves`, a dying player rolls a death save as their turn starts; otherwise
+    /// the save is left to be [recorded](super::Player::death_save) by hand.
+    pub fn next_turn(&mut self, roll_death_saves: bool) -> TurnChange {
+        let mut expired = Vec::new();
         if self.order.is_empty() {
-            return;
+            return TurnChange::default();
         }
 
-        if let Some(participant) = self.participants.get_mut(&self.order[self.turn as usize]) {
+        let ending = self.order[self.turn as usize];
+        let time = self.time();
+        if let Some(participant) = self.participants.get_mut(&ending) {
             participant.end_turn();
         }
 
+        self.expire(&mut expired, |condition, instigator| {
+            condition.expires_on_turn_end(instigator == ending, time)
+        });
+
         self.turn += 1;
         if self.turn >= self.order.len() as u32 {
             self.turn = 0;
@@ -97,9 +145,175 @@ impl Game {
 
         self.turn_started = Utc::now();
 
-        if let Some(participant) = self.participants.get_mut(&self.order[self.turn as usize]) {
+        let beginning = self.order[self.turn as usize];
+        let time = self.time();
+        self.expire(&mut expired, |condition, instigator| {
+            condition.expires_on_turn_start(instigator == beginning, time)
+        });
+
+        if let Some(participant) = self.participants.get_mut(&beginning) {
             participant.begin_turn();
         }
+
+        TurnChange {
+            expired,
+            death_save: roll_death_saves
+                .then(|| self.roll_death_save(beginning))
+                .flatten(),
+        }
+    }
+
+    /// Rolls a death save for a participant if they are a dying player, using the game's random number generator so
+    /// that the roll can be replayed.
+    fn roll_death_save(&mut self, id: ParticipantId) -> Option<DeathSaveRoll> {
+        if !matches!(self.participants.get(&id), Some(Participant::Player(player)) if player.is_dying())
+        {
+            return None;
+        }
+
+        let time = self.time();
+        let (seed, stream) = self.next_stream();
+        let roll = DiceExpr::parse("d20")
+            .and_then(|d20| d20.roll_seeded(seed, stream, Critical::default(), false, None))
+            .ok()?;
+        let save = DeathSave::from_roll(roll.value);
+
+        let Some(Participant::Player(player)) = self.participants.get_mut(&id) else {
+            return None;
+        };
+        player.death_save(time, save);
+
+        Some(DeathSaveRoll {
+            participant: id,
+            roll,
+            save,
+            saves: player.death_saves,
+        })
+    }
+
+    /// Applies damage to a participant. If they are concentrating, returns the concentration check the damage calls
+    /// for, with a DC [capped](concentration_dc) if `capped_dc`. The check is rolled with the participant's
+    /// Constitution modifier if `roll_check`; otherwise it is left to be rolled by hand. A participant left
+    /// incapacitated or dead loses concentration without a check.
+    pub fn damage(
+        &mut self,
+        target: ParticipantId,
+        damage: Damage,
+        critical: bool,
+        capped_dc: bool,
+        roll_check: bool,
+    ) -> Result<Option<ConcentrationCheck>, String> {
+        let time = self.time();
+        let Some(participant) = self.participants.get_mut(&target) else {
+            return Err(format!("No participant found with id {target}"));
+        };
+
+        let concentrating = participant.has_condition(conditions::CONCENTRATING);
+        match participant {
+            Participant::Monster(monster) => monster.damage(time, damage),
+            Participant::Player(player) => player.damage(time, damage, critical),
+            Participant::Lair(_) => return Err("Lairs may not be damaged".into()),
+        }
+
+        let amount = damage.amount();
+        if !concentrating || amount == Some(0) {
+            return Ok(None);
+        }
+
+        let dc = concentration_dc(amount.unwrap_or_default(), capped_dc);
+        let incapacitated = participant.has_condition(conditions::INCAPACITATED)
+            || participant.has_condition(conditions::DEAD);
+
+        let roll = if incapacitated || !roll_check {
+            None
+        } else {
+            let (seed, stream) = self.next_stream();
+            let bindings = self
+                .participants
+                .get(&target)
+                .and_then(Participant::bindings);
+            let roll = DiceExpr::parse("d20 + @con").and_then(|save| {
+                save.roll_seeded(
+                    seed,
+                    stream,
+                    Critical::default(),
+                    false,
+                    bindings.as_ref().map(|b| b as _),
+                )
+            });
+            Some(roll.map_err(|e| e.to_string())?)
+        };
+
+        let broken = incapacitated || roll.as_ref().is_some_and(|roll| roll.value < dc as i32);
+        let ended = if broken {
+            self.break_concentration(target)
+        } else {
+            vec![]
+        };
+
+        Ok(Some(ConcentrationCheck {
+            participant: target,
+            dc,
+            roll,
+            broken,
+            ended,
+        }))
+    }
+
+    /// Ends a participant's concentration, returning the concentration condition along with the conditions sustained
+    /// by it, on the participant or others, and any conditions only those implied.
+    pub fn break_concentration(&mut self, id: ParticipantId) -> Vec<ExpiredCondition> {
+        let mut ended = Vec::new();
+        self.expire(&mut ended, |condition, instigator| {
+            instigator == id
+                && (condition.name == conditions::CONCENTRATING || condition.concentration)
+        });
+        ended
+    }
+
+    /// Removes each condition for which `expires` returns true, given the condition and its instigator, adding it and
+    /// any conditions that only it implied to `expired`. Conditions without an instigator, or whose instigator has left
+    /// the game, are treated as self-inflicted.
+    fn expire(
+        &mut self,
+        expired: &mut Vec<ExpiredCondition>,
+        expires: impl Fn(&Condition, ParticipantId) -> bool,
+    ) {
+        for &participant in &self.order {
+            let Some(conditions) = self
+                .participants
+                .get_mut(&participant)
+                .and_then(Participant::conditions_mut)
+            else {
+                continue;
+            };
+
+            conditions.retain(|condition| {
+                let instigator = condition
+                    .instigator
+                    .filter(|instigator| self.order.contains(instigator))
+                    .unwrap_or(participant);
+
+                if expires(condition, instigator) {
+                    expired.push(ExpiredCondition {
+                        participant,
+                        condition: condition.clone(),
+                    });
+                    false
+                } else {
+                    true
+                }
+            });
+
+            expired.extend(
+                remove_orphaned_implications(conditions)
+                    .into_iter()
+                    .map(|condition| ExpiredCondition {
+                        participant,
+                        condition,
+                    }),
+            );
+        }
     }
 
     pub fn time(&self) -> Time {
@@ -180,6 +394,10 @@ impl Game {
     // }
 }
 
+fn new_seed() -> u64 {
+    random_seed(&mut rand::thread_rng())
+}
+
 impl Default for Game {
     fn default() -> Self {
         Self {
@@ -189,12 +407,19 @@ impl Default for Game {
             turn: 0,
             game_started: Utc::now(),
             turn_started: Utc::now(),
-            rng: StdRng::from_entropy(),
+            seed: new_seed(),
+            stream: 0,
             next_id: ParticipantId(1),
         }
     }
 }
 
+impl From<ParticipantId> for u32 {
+    fn from(id: ParticipantId) -> Self {
+        id.0
+    }
+}
+
 impl Display for ParticipantId {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         write!(f, "ParticipantId({})", self.0)
@@ -6,7 +6,7 @@ use ts_rs::TS;
 
 use crate::game::{conditions, time::Time, Action, Damage, Healing};
 
-use super::{Condition, Stats};
+use super::{proficiency_bonus, Bindings, Condition, Stats};
 
 /// A monster instance in an encounter.
 ///
@@ -91,25 +91,30 @@ pub struct Monster {
 }
 
 impl Monster {
+    /// Returns the monster's proficiency bonus, determined by its challenge
+    /// rating.
+    pub fn proficiency_bonus(&self) -> i32 {
+        // Challenge ratings below 1 occupy the first four indices.
+        proficiency_bonus(self.cr.saturating_sub(3))
+    }
+
+    /// Returns the variables available to dice expressions rolled for the
+    /// monster.
+    pub fn bindings(&self) -> Bindings<'_> {
+        Bindings {
+            stats: &self.stats,
+            proficiency_bonus: self.proficiency_bonus(),
+        }
+    }
+
     pub fn begin_turn(&mut self) {
         self.action = true;
         self.reaction = true;
         self.bonus_action = true;
         self.legendary_actions.fill(true);
-        // self.conditions = self
-        //     .conditions
-        //     .into_iter()
-        //     .filter_map(|c| c.begin_turn())
-        //     .collect();
     }
 
-    pub fn end_turn(&mut self) {
-        // self.conditions = self
-        //     .conditions
-        //     .into_iter()
-        //     .filter_map(|c| c.end_turn())
-        //     .collect();
-    }
+    pub fn end_turn(&mut self) {}
 
     pub fn has_condition(&self, condition_name: &str) -> bool {
         self.conditions.iter().any(|c| c.name == condition_name)
@@ -127,22 +132,18 @@ impl Monster {
     }
 
     pub fn damage(&mut self, time: Time, damage: Damage) {
-        let damage_amount = match damage {
-            Damage::Damage { amount } => amount as i32,
-            Damage::HalfDamage { amount } => (amount / 2) as i32,
-            Damage::DoubleDamage { amount } => (amount * 2) as i32,
-            Damage::Kill => {
-                self.hp = 0;
-                self.temp_hp = 0;
-                if !self.has_condition(conditions::BLOODIED) {
-                    self.conditions.push(Condition::bloodied(time));
-                }
-                if !self.has_condition(conditions::DEAD) {
-                    self.conditions.push(Condition::dead(time));
-                }
-                return;
+        let Some(amount) = damage.amount() else {
+            self.hp = 0;
+            self.temp_hp = 0;
+            if !self.has_condition(conditions::BLOODIED) {
+                self.conditions.push(Condition::bloodied(time));
             }
+            if !self.has_condition(conditions::DEAD) {
+                self.conditions.push(Condition::dead(time));
+            }
+            return;
         };
+        let damage_amount = amount as i32;
 
         let remaining = if self.temp_hp > 0 {
             let temp_damage = damage_amount.min(self.temp_hp);
@@ -174,6 +175,10 @@ impl Monster {
             Healing::SetTempHp { amount } => {
                 self.temp_hp = amount as i32;
             }
+            Healing::SetMaxHp { amount } => {
+                self.max_hp = amount as i32;
+                self.hp = self.hp.min(self.max_hp);
+            }
         };
 
         if self.hp > 0 {
@@ -6,7 +6,7 @@ use std::cmp::Ordering;
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
-use crate::game::{Lair, Monster, Player};
+use crate::game::{Bindings, Condition, Lair, Monster, Player};
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
 #[ts(export)]
@@ -36,6 +36,15 @@ impl Participant {
         }
     }
 
+    /// Returns the participant's free-form notes.
+    pub fn notes(&self) -> &str {
+        match self {
+            Participant::Lair(lair) => &lair.notes,
+            Participant::Monster(monster) => &monster.notes,
+            Participant::Player(player) => &player.notes,
+        }
+    }
+
     pub fn initiative(&self) -> u32 {
         match self {
             Participant::Lair(_) => 20,
@@ -52,6 +61,34 @@ impl Participant {
         }
     }
 
+    /// Returns the variables available to dice expressions rolled for the
+    /// participant, or `None` for participants without ability scores.
+    pub fn bindings(&self) -> Option<Bindings<'_>> {
+        match self {
+            Participant::Lair(_) => None,
+            Participant::Monster(monster) => Some(monster.bindings()),
+            Participant::Player(player) => Some(player.bindings()),
+        }
+    }
+
+    /// Returns the participant's conditions, or `None` for participants that
+    /// cannot have conditions.
+    pub fn conditions_mut(&mut self) -> Option<&mut Vec<Condition>> {
+        match self {
+            Participant::Lair(_) => None,
+            Participant::Monster(monster) => Some(&mut monster.conditions),
+            Participant::Player(player) => Some(&mut player.conditions),
+        }
+    }
+
+    pub fn has_condition(&self, name: &str) -> bool {
+        match self {
+            Participant::Lair(_) => false,
+            Participant::Monster(monster) => monster.has_condition(name),
+            Participant::Player(player) => player.has_condition(name),
+        }
+    }
+
     pub fn begin_turn(&mut self) {
         match self {
             Participant::Lair(_) => {}
@@ -4,7 +4,14 @@
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
-use crate::game::{conditions::Condition, Action, Class, Stats};
+use crate::game::{
+    add_condition,
+    conditions::{self, Condition},
+    death_saves::{DeathSave, DeathSaves},
+    proficiency_bonus, remove_conditions,
+    time::Time,
+    Action, Bindings, Class, Damage, Healing, Stats,
+};
 
 /// A player instance in an encounter.
 ///
@@ -43,6 +50,23 @@ pub struct Player {
     /// Tiebreaker value for initiative ties.
     pub tiebreaker: i32,
 
+    /// Current hit points.
+    #[serde(default)]
+    pub hp: i32,
+
+    /// Current temporary hit points.
+    #[serde(default)]
+    pub temp_hp: i32,
+
+    /// Maximum hit points, or 0 if not yet known, in which case healing is not capped and the player is never
+    /// bloodied.
+    #[serde(default)]
+    pub max_hp: i32,
+
+    /// Death saving throws made since the player last dropped to 0 hit points.
+    #[serde(default)]
+    pub death_saves: DeathSaves,
+
     /// Whether the player's standard action is available.
     pub action: bool,
 
@@ -64,24 +88,27 @@ impl Player {
         self.classes.iter().map(|c| c.level).sum()
     }
 
+    /// Returns the player's proficiency bonus, determined by their total level.
+    pub fn proficiency_bonus(&self) -> i32 {
+        proficiency_bonus(self.total_level())
+    }
+
+    /// Returns the variables available to dice expressions rolled for the
+    /// player.
+    pub fn bindings(&self) -> Bindings<'_> {
+        Bindings {
+            stats: &self.stats,
+            proficiency_bonus: self.proficiency_bonus(),
+        }
+    }
+
     pub fn begin_turn(&mut self) {
         self.action = true;
         self.reaction = true;
         self.bonus_action = true;
-        // self.conditions = self
-        //     .conditions
-        //     .into_iter()
-        //     .filter_map(|c| c.begin_turn())
-        //     .collect();
     }
 
-    pub fn end_turn(&mut self) {
-        // self.conditions = self
-        //     .conditions
-        //     .into_iter()
-        //     .filter_map(|c| c.end_turn())
-        //     .collect();
-    }
+    pub fn end_turn(&mut self) {}
 
     pub fn set_action(&mut self, action: Action, available: bool) -> Result<(), ()> {
         match action {
@@ -93,4 +120,192 @@ impl Player {
 
         Ok(())
     }
+
+    pub fn has_condition(&self, condition_name: &str) -> bool {
+        self.conditions.iter().any(|c| c.name == condition_name)
+    }
+
+    /// Applies damage as for a monster, except that a player's hit points do not drop below 0 and a player reduced to
+    /// 0 hit points falls unconscious rather than dying, unless killed outright. Damage taken at 0 hit points counts as
+    /// a failed death save, or two if `critical`.
+    pub fn damage(&mut self, time: Time, damage: Damage, critical: bool) {
+        let Some(amount) = damage.amount() else {
+            self.hp = 0;
+            self.temp_hp = 0;
+            self.death_saves.dying = false;
+            if !self.has_condition(conditions::BLOODIED) {
+                self.conditions.push(Condition::bloodied(time));
+            }
+            if !self.has_condition(conditions::DEAD) {
+                self.conditions.push(Condition::dead(time));
+            }
+            return;
+        };
+        let damage_amount = amount as i32;
+
+        let remaining = if self.temp_hp > 0 {
+            let temp_damage = damage_amount.min(self.temp_hp);
+            self.temp_hp -= temp_damage;
+            damage_amount - temp_damage
+        } else {
+            damage_amount
+        };
+
+        if self.hp <= 0 {
+            if remaining > 0 {
+                self.death_saves.stable = false;
+                self.death_saves.dying = true;
+                let save = if critical {
+                    DeathSave::CriticalFailure
+                } else {
+                    DeathSave::Failure
+                };
+                self.death_save(time, save);
+            }
+            return;
+        }
+
+        self.hp = self.hp.saturating_sub(remaining).max(0);
+
+        if self.is_bloodied() && !self.has_condition(conditions::BLOODIED) {
+            self.conditions.push(Condition::bloodied(time));
+        }
+
+        if self.hp == 0 {
+            self.death_saves = DeathSaves {
+                dying: true,
+                ..DeathSaves::default()
+            };
+            if !self.has_condition(conditions::UNCONSCIOUS) {
+                add_condition(&mut self.conditions, Condition::unconscious(time));
+            }
+        }
+    }
+
+    /// Returns whether the player was reduced to 0 hit points by damage and must make death saves, having neither
+    /// stabilized, died nor regained hit points since.
+    pub fn is_dying(&self) -> bool {
+        self.death_saves.dying && !self.has_condition(conditions::DEAD)
+    }
+
+    /// Records a death saving throw. A critical success restores 1 hit point, and three failures kill the player. Has
+    /// no effect unless the player [is dying](Self::is_dying).
+    pub fn death_save(&mut self, time: Time, save: DeathSave) {
+        if !self.is_dying() {
+            return;
+        }
+
+        if save == DeathSave::CriticalSuccess {
+            self.heal(Healing::SetHp { amount: 1 });
+            return;
+        }
+
+        self.death_saves.record(save);
+        if self.death_saves.is_dead() {
+            self.conditions.push(Condition::dead(time));
+        }
+    }
+
+    /// Applies healing as for a monster. A player who regains hit points also regains consciousness.
+    pub fn heal(&mut self, healing: Healing) {
+        match healing {
+            Healing::Heal { amount } => {
+                self.hp = self.hp.max(0) + amount as i32;
+                if self.max_hp > 0 {
+                    self.hp = self.hp.min(self.max_hp);
+                }
+            }
+            Healing::SetHp { amount } => {
+                self.hp = amount as i32;
+            }
+            Healing::SetTempHp { amount } => {
+                self.temp_hp = amount as i32;
+            }
+            Healing::SetMaxHp { amount } => {
+                self.max_hp = amount as i32;
+                if self.max_hp > 0 {
+                    self.hp = self.hp.min(self.max_hp);
+                }
+            }
+        };
+
+        if self.hp > 0 {
+            self.death_saves = DeathSaves::default();
+            let revived: Vec<Condition> = self
+                .conditions
+                .iter()
+                .filter(|c| c.name == conditions::DEAD || c.name == conditions::UNCONSCIOUS)
+                .cloned()
+                .collect();
+            remove_conditions(&mut self.conditions, &revived);
+        }
+
+        if !self.is_bloodied() {
+            self.conditions.retain(|c| c.name != conditions::BLOODIED);
+        }
+    }
+
+    /// Returns whether the player is at or below half of their known maximum hit points.
+    fn is_bloodied(&self) -> bool {
+        self.max_hp > 0 && self.hp <= self.max_hp / 2
+    }
+}
+
+#[cfg(test)]
+impl Player {
+    /// Creates a classless player with average ability scores and full hit points, for tests.
+    pub(crate) fn test(name: &str, initiative: u32, max_hp: i32) -> Self {
+        Player {
+            name: name.to_string(),
+            classes: vec![],
+            stats: Stats {
+                str: 10,
+                dex: 10,
+                con: 10,
+                int: 10,
+                wis: 10,
+                cha: 10,
+            },
+            ac: 10,
+            initiative_bonus: 0,
+            small_portrait: None,
+            full_portrait: None,
+            initiative,
+            tiebreaker: 0,
+            hp: max_hp,
+            temp_hp: 0,
+            max_hp,
+            death_saves: DeathSaves::default(),
+            action: true,
+            reaction: true,
+            bonus_action: true,
+            notes: String::new(),
+            conditions: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_max_hp() {
+        let time = Time::new(1, 10);
+        let mut alice = Player::test("Alice", 10, 0);
+        alice.hp = 30;
+
+        alice.damage(time, Damage::Damage { amount: 20 }, false);
+        alice.heal(Healing::Heal { amount: 25 });
+        assert_eq!(alice.hp, 35);
+        assert!(!alice.has_condition(conditions::BLOODIED));
+
+        alice.heal(Healing::SetMaxHp { amount: 30 });
+        assert_eq!((alice.hp, alice.max_hp), (30, 30));
+        alice.damage(time, Damage::Damage { amount: 15 }, false);
+        assert!(alice.has_condition(conditions::BLOODIED));
+        alice.heal(Healing::Heal { amount: 20 });
+        assert_eq!(alice.hp, 30);
+        assert!(!alice.has_condition(conditions::BLOODIED));
+    }
 }
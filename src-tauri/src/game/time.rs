@@ -44,6 +44,13 @@ impl Time {
     pub fn secs(&self) -> u32 {
         self.total_secs() % 60
     }
+
+    /// Returns whether this instant is at or after `other` in the order of play. Within a round, turns proceed from
+    /// the highest initiative to the lowest.
+    pub fn has_reached(&self, other: Time) -> bool {
+        self.round > other.round
+            || (self.round == other.round && self.initiative <= other.initiative)
+    }
 }
 
 impl Duration {
@@ -6,7 +6,11 @@ use std::vec;
 use serde::Deserialize;
 use tauri::{AppHandle, Emitter, State as TauriState};
 
-use crate::game::{Action, Condition, Damage, Expiry, Game, Healing, Participant, ParticipantId};
+use crate::config::{ConcentrationChecks, DeathSaveRolls, RulesVersion};
+use crate::game::{
+    add_condition, Action, ConcentrationCheck, Condition, Damage, DeathSave, ExpiredCondition,
+    Expiry, Game, Healing, Participant, ParticipantId, TurnChange, CONCENTRATING,
+};
 use crate::state::AppStateMutex;
 
 #[tauri::command]
@@ -27,25 +31,103 @@ pub async fn new_game(app: AppHandle, state: TauriState<'_, AppStateMutex>) -> R
     Ok(())
 }
 
+/// Advances to the next turn, removing any conditions that expired and
+/// rolling a death save for a dying player if the open campaign's rules say
+/// so. The expired conditions are also emitted as `conditions-expired`, and the
+/// death save as `death-save-rolled`, so that they can be announced.
 #[tauri::command]
-pub async fn next_turn(app: AppHandle, state: TauriState<'_, AppStateMutex>) -> Result<(), String> {
+pub async fn next_turn(
+    app: AppHandle,
+    state: TauriState<'_, AppStateMutex>,
+) -> Result<TurnChange, String> {
     let mut state = state.lock().await;
+    let roll_death_saves = state
+        .campaign
+        .as_ref()
+        .is_none_or(|c| c.settings.rules.death_saves == DeathSaveRolls::Rolled);
+
+    let mut change = TurnChange::default();
     state
         .encounter
-        .mutate(app, |game| {
-            game.next_turn();
+        .mutate(app.clone(), |game| {
+            change = game.next_turn(roll_death_saves);
             Ok(())
         })
         .await?;
-    Ok(())
+
+    if !change.expired.is_empty() {
+        app.emit("conditions-expired", &change.expired)
+            .map_err(|e| e.to_string())?;
+    }
+
+    if let Some(death_save) = &change.death_save {
+        app.emit("death-save-rolled", death_save)
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(change)
 }
 
+/// Applies damage to a participant, returning the concentration check it calls for if they are concentrating.
 #[tauri::command]
 pub async fn damage(
     app: AppHandle,
     state: TauriState<'_, AppStateMutex>,
     target: ParticipantId,
     damage: Damage,
+    critical: Option<bool>,
+) -> Result<Option<ConcentrationCheck>, String> {
+    let mut state = state.lock().await;
+    let rules = state
+        .campaign
+        .as_ref()
+        .map(|c| c.settings.rules.clone())
+        .unwrap_or_default();
+
+    let mut check = None;
+    state
+        .encounter
+        .mutate(app, |game| {
+            check = game.damage(
+                target,
+                damage,
+                critical.unwrap_or(false),
+                rules.version == RulesVersion::Srd52,
+                rules.concentration_checks == ConcentrationChecks::Rolled,
+            )?;
+            Ok(())
+        })
+        .await?;
+    Ok(check)
+}
+
+/// Ends a participant's concentration, such as after a concentration check rolled by hand fails, returning the
+/// conditions that ended with it.
+#[tauri::command]
+pub async fn break_concentration(
+    app: AppHandle,
+    state: TauriState<'_, AppStateMutex>,
+    target: ParticipantId,
+) -> Result<Vec<ExpiredCondition>, String> {
+    let mut state = state.lock().await;
+    let mut ended = vec![];
+    state
+        .encounter
+        .mutate(app, |game| {
+            ended = game.break_concentration(target);
+            Ok(())
+        })
+        .await?;
+    Ok(ended)
+}
+
+/// Records a death saving throw made by hand for a dying player.
+#[tauri::command]
+pub async fn record_death_save(
+    app: AppHandle,
+    state: TauriState<'_, AppStateMutex>,
+    target: ParticipantId,
+    save: DeathSave,
 ) -> Result<(), String> {
     let mut state = state.lock().await;
     state
@@ -56,12 +138,15 @@ pub async fn damage(
                 return Err(format!("No participant found with id {target}"));
             };
 
-            let Participant::Monster(monster) = participant else {
-                return Err(format!("Participant with id {target} is not a monster"));
+            let Participant::Player(player) = participant else {
+                return Err(format!("Participant with id {target} is not a player"));
             };
 
-            monster.damage(time, damage);
+            if !player.is_dying() {
+                return Err(format!("{} is not dying", player.name));
+            }
 
+            player.death_save(time, save);
             Ok(())
         })
         .await?;
@@ -83,11 +168,11 @@ pub async fn heal(
                 return Err(format!("No participant found with id {target}"));
             };
 
-            let Participant::Monster(monster) = participant else {
-                return Err(format!("Participant with id {target} is not a monster"));
-            };
-
-            monster.heal(healing);
+            match participant {
+                Participant::Monster(monster) => monster.heal(healing),
+                Participant::Player(player) => player.heal(healing),
+                Participant::Lair(_) => return Err("Lairs may not be healed".into()),
+            }
 
             Ok(())
         })
@@ -95,6 +180,8 @@ pub async fn heal(
     Ok(())
 }
 
+/// Adds conditions to a participant, along with the conditions they imply. The instigator of a condition sustained
+/// by concentration begins concentrating, if they are not already.
 #[tauri::command]
 pub async fn add_conditions(
     app: AppHandle,
@@ -111,10 +198,29 @@ pub async fn add_conditions(
                 return Err(format!("No participant found with id {target}"));
             };
 
-            match participant {
-                Participant::Monster(monster) => monster.conditions.extend(conditions),
-                Participant::Player(player) => player.conditions.extend(conditions),
-                Participant::Lair(_) => return Err("Lairs may not have conditions".into()),
+            let Some(existing) = participant.conditions_mut() else {
+                return Err("Lairs may not have conditions".into());
+            };
+
+            let concentrators: Vec<ParticipantId> = conditions
+                .iter()
+                .filter(|condition| condition.concentration)
+                .map(|condition| condition.instigator.unwrap_or(target))
+                .collect();
+            for condition in conditions {
+                add_condition(existing, condition);
+            }
+
+            for concentrator in concentrators {
+                let Some(participant) = game.participants.get_mut(&concentrator) else {
+                    continue;
+                };
+                if participant.has_condition(CONCENTRATING) {
+                    continue;
+                }
+                if let Some(existing) = participant.conditions_mut() {
+                    existing.push(Condition::concentrating(time));
+                }
             }
 
             Ok(())
@@ -123,29 +229,48 @@ pub async fn add_conditions(
     Ok(())
 }
 
+/// Removes conditions from a participant, along with any conditions they
+/// implied that nothing else still implies.
+#[tauri::command]
+pub async fn remove_conditions(
+    app: AppHandle,
+    state: TauriState<'_, AppStateMutex>,
+    target: ParticipantId,
+    conditions: Vec<Condition>,
+) -> Result<(), String> {
+    let mut state = state.lock().await;
+    state
+        .encounter
+        .mutate(app, |game| {
+            let Some(participant) = game.participants.get_mut(&target) else {
+                return Err(format!("No participant found with id {target}"));
+            };
+
+            let Some(existing) = participant.conditions_mut() else {
+                return Err("Lairs may not have conditions".into());
+            };
+
+            crate::game::remove_conditions(existing, &conditions);
+            Ok(())
+        })
+        .await?;
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn undo(app: AppHandle, state: TauriState<'_, AppStateMutex>) -> Result<(), String> {
     let mut state = state.lock().await;
-    if state.encounter.undo_stack.len() > 1 {
-        let game = state.encounter.undo_stack.pop().unwrap();
-        state.encounter.redo_stack.push(game.clone());
-        app.emit("game-updated", game).map_err(|e| e.to_string())?;
-        Ok(())
-    } else {
-        return Err("Undo stack is empty".to_string());
-    }
+    let game = state.encounter.undo()?;
+    app.emit("game-updated", game).map_err(|e| e.to_string())?;
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn redo(app: AppHandle, state: TauriState<'_, AppStateMutex>) -> Result<(), String> {
     let mut state = state.lock().await;
-    if let Some(game) = state.encounter.redo_stack.pop() {
-        state.encounter.undo_stack.push(game.clone());
-        app.emit("game-updated", game).map_err(|e| e.to_string())?;
-        Ok(())
-    } else {
-        return Err("Redo stack is empty".to_string());
-    }
+    let game = state.encounter.redo()?;
+    app.emit("game-updated", game).map_err(|e| e.to_string())?;
+    Ok(())
 }
 
 #[tauri::command]
@@ -6,8 +6,9 @@ use tauri::Manager;
 use crate::{
     game::{
         time::{Duration, Time},
-        Class, Condition, Game, Lair, Monster, Player, Stats,
+        Class, Condition, DeathSaves, Game, Lair, Monster, Player, Stats,
     },
+    services::RollLog,
     state::{AppState, AppStateMutex, EncounterState},
 };
 
@@ -47,6 +48,10 @@ pub fn run() {
         notes: "Lawful stupid murder hobo with an angsty backstory who roams the land in search of justice or something.".into(),
         initiative: 15,
         tiebreaker: 123,
+        hp: 44,
+        temp_hp: 0,
+        max_hp: 44,
+        death_saves: DeathSaves::default(),
         action: true,
         reaction: true,
         bonus_action: true,
@@ -148,6 +153,7 @@ pub fn run() {
             undo_stack: vec![game],
             redo_stack: vec![],
         },
+        roll_log: RollLog::default(),
     });
 
     tauri::Builder::default()
@@ -163,14 +169,27 @@ pub fn run() {
             campaign_commands::close_campaign,
             campaign_commands::get_current_campaign,
             dice_commands::roll,
+            dice_commands::roll_for,
+            dice_commands::roll_critical,
+            dice_commands::get_notes,
+            dice_commands::roll_note,
+            dice_commands::replay_roll,
+            dice_commands::check_dice,
+            dice_commands::distribution,
+            dice_commands::chance,
+            dice_commands::get_roll_log,
+            dice_commands::clear_roll_log,
             game_commands::new_game,
             game_commands::get_game,
             game_commands::next_turn,
             game_commands::undo,
             game_commands::redo,
             game_commands::damage,
+            game_commands::break_concentration,
             game_commands::heal,
+            game_commands::record_death_save,
             game_commands::add_conditions,
+            game_commands::remove_conditions,
             game_commands::set_action,
         ])
         .run(tauri::generate_context!())
@@ -6,9 +6,12 @@
 //! This module provides:
 //! - Portrait resolution with prioritized search
 //! - Encounter savepoint management
+//! - In-memory roll history while no campaign is open
 
 mod portrait;
+mod roll_log;
 mod savepoint;
 
 pub use portrait::{PortraitService, PortraitSize, PortraitSubject};
+pub use roll_log::RollLog;
 pub use savepoint::SavepointService;
@@ -0,0 +1,113 @@
+// Copyright (c) 2025 Jonathon B. Cobb
+// Licensed under the MIT License
+
+//! In-memory roll log.
+//!
+//! Keeps the history of dice rolls while no campaign is open. Once a campaign
+//! is open, rolls are recorded in its database instead.
+
+use crate::db::{RollLogData, RollLogFilter, RollLogRecord};
+
+/// A roll log held in memory, with the same paging and filtering as the roll
+/// log in the campaign database.
+#[derive(Debug, Default)]
+pub struct RollLog {
+    records: Vec<RollLogRecord>,
+}
+
+impl RollLog {
+    /// Records a roll in the log.
+    ///
+    /// Returns the ID of the newly recorded roll.
+    pub fn insert(&mut self, roll: RollLogData) -> i64 {
+        let id = self.records.last().map_or(1, |record| record.id + 1);
+        self.records.push(RollLogRecord::new(id, roll));
+        id
+    }
+
+    /// Gets a roll by ID.
+    pub fn get(&self, id: i64) -> Option<&RollLogRecord> {
+        self.records.iter().find(|record| record.id == id)
+    }
+
+    /// Lists a page of the rolls that match `filter`.
+    ///
+    /// Returns rolls from newest to oldest, skipping the first `offset`.
+    pub fn list(&self, filter: &RollLogFilter, offset: u32, limit: u32) -> Vec<RollLogRecord> {
+        self.records
+            .iter()
+            .rev()
+            .filter(|record| filter.matches(record))
+            .skip(offset as usize)
+            .take(limit as usize)
+            .cloned()
+            .collect()
+    }
+
+    /// Removes every roll from the log.
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::config::CriticalHits;
+
+    fn roll(expression: &str, roller: Option<&str>) -> RollLogData {
+        RollLogData {
+            timestamp: "2025-01-01T00:00:00+00:00".to_string(),
+            expression: expression.to_string(),
+            value: 10,
+            dice: Vec::new(),
+            explanation: String::new(),
+            seed: 0,
+            stream: 0,
+            label: None,
+            roller: roller.map(str::to_string),
+            critical: false,
+            critical_rule: CriticalHits::default(),
+            participant: None,
+            variables: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_roll_log_paging() {
+        let mut log = RollLog::default();
+        for i in 0..5 {
+            log.insert(roll(&format!("d20 + {i}"), None));
+        }
+
+        let page = log.list(&RollLogFilter::default(), 1, 2);
+        let expressions: Vec<_> = page.iter().map(|r| r.expression.as_str()).collect();
+        assert_eq!(expressions, ["d20 + 3", "d20 + 2"]);
+    }
+
+    #[test]
+    fn test_roll_log_filter() {
+        let mut log = RollLog::default();
+        log.insert(roll("d20 + 4", Some("Goblin 1")));
+        log.insert(roll("1d6 + 2", Some("Goblin 1")));
+        log.insert(roll("D20 + 1", Some("Ranger")));
+
+        let filter = RollLogFilter {
+            roller: Some("Goblin 1".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(log.list(&filter, 0, 10).len(), 2);
+
+        let filter = RollLogFilter {
+            expression: Some("d20".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(log.list(&filter, 0, 10).len(), 2);
+
+        log.clear();
+        assert!(log.list(&RollLogFilter::default(), 0, 10).is_empty());
+        assert_eq!(log.insert(roll("d4", None)), 1);
+    }
+}
@@ -181,6 +181,20 @@ mod tests {
         assert_eq!(loaded.turn_number, 0);
     }
 
+    #[test]
+    fn test_savepoint_preserves_rng() {
+        let temp = tempdir().unwrap();
+        let service = SavepointService::new(temp.path(), 50);
+
+        let mut game = Game::new();
+        game.next_stream();
+        game.next_stream();
+        service.write(&game).unwrap();
+
+        let mut loaded = service.read_latest().unwrap().unwrap().game;
+        assert_eq!(loaded.next_stream(), (game.seed, 2));
+    }
+
     #[test]
     fn test_savepoint_pruning() {
         let temp = tempdir().unwrap();
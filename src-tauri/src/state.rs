@@ -8,6 +8,7 @@ use tauri::{async_runtime::Mutex, AppHandle, Emitter};
 use crate::config::{AppPaths, AppSettings, CampaignSettings};
 use crate::db::CampaignDb;
 use crate::game::Game;
+use crate::services::RollLog;
 
 /// Application state managed by Tauri.
 pub struct AppState {
@@ -22,6 +23,9 @@ pub struct AppState {
 
     /// In-memory encounter state with undo/redo.
     pub encounter: EncounterState,
+
+    /// Rolls made while no campaign is open.
+    pub roll_log: RollLog,
 }
 
 pub type AppStateMutex = Mutex<AppState>;
@@ -63,4 +67,58 @@ impl EncounterState {
             Err("No game found".to_string())
         }
     }
+
+    /// Reverts the last change, returning the game that was undone. Rolls made since are not undone, so the game
+    /// returned to keeps drawing from the streams after them rather than repeating their results.
+    pub fn undo(&mut self) -> Result<Game, String> {
+        if self.undo_stack.len() < 2 {
+            return Err("Undo stack is empty".to_string());
+        }
+
+        let Some(game) = self.undo_stack.pop() else {
+            return Err("Undo stack is empty".to_string());
+        };
+        if let Some(current) = self.undo_stack.last_mut() {
+            current.stream = current.stream.max(game.stream);
+        }
+        self.redo_stack.push(game.clone());
+        Ok(game)
+    }
+
+    /// Reapplies the last change that was undone, returning the game it restores, which keeps the streams used by
+    /// any rolls made since the change was undone.
+    pub fn redo(&mut self) -> Result<Game, String> {
+        let Some(mut game) = self.redo_stack.pop() else {
+            return Err("Redo stack is empty".to_string());
+        };
+        if let Some(current) = self.undo_stack.last() {
+            game.stream = game.stream.max(current.stream);
+        }
+        self.undo_stack.push(game.clone());
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_keeps_streams() {
+        let mut encounter = EncounterState {
+            undo_stack: vec![Game::new()],
+            redo_stack: vec![],
+        };
+
+        let mut changed = encounter.undo_stack[0].clone();
+        changed.next_streams(3);
+        encounter.undo_stack.push(changed);
+
+        encounter.undo().unwrap();
+        assert_eq!(encounter.undo_stack[0].next_stream().1, 3);
+
+        let redone = encounter.redo().unwrap();
+        assert_eq!(redone.stream, 4);
+        assert!(encounter.redo().is_err());
+    }
 }
@@ -5,16 +5,27 @@ import { messageBoxStore } from "$lib/MessageBox";
 import { invoke, type InvokeArgs } from "@tauri-apps/api/core";
 import type { Game } from "./Game";
 import type { Action } from "./gen/Action";
+import type { Chance } from "./gen/Chance";
+import type { ConcentrationCheck } from "./gen/ConcentrationCheck";
 import type { Condition } from "./gen/Condition";
 import type { Damage } from "./gen/Damage";
+import type { DeathSave } from "./gen/DeathSave";
+import type { DiceCheck } from "./gen/DiceCheck";
+import type { Distribution } from "./gen/Distribution";
+import type { ExpiredCondition } from "./gen/ExpiredCondition";
 import type { Healing } from "./gen/Healing";
+import type { Notes } from "./gen/Notes";
 import type { Roll } from "./gen/Roll";
+import type { RollLogFilter } from "./gen/RollLogFilter";
+import type { RollLogRecord } from "./gen/RollLogRecord";
+import type { TurnChange } from "./gen/TurnChange";
 
 export namespace gameCommands {
   export interface DamageArgs {
     [key: string]: unknown;
     target: number,
     damage: Damage,
+    critical?: boolean,
   }
 
   export interface HealArgs {
@@ -23,6 +34,12 @@ export namespace gameCommands {
     healing: Healing
   }
 
+  export interface DeathSaveArgs {
+    [key: string]: unknown;
+    target: number,
+    save: DeathSave
+  }
+
   export interface SetActionArgs {
     [key: string]: unknown;
     target: number,
@@ -38,23 +55,81 @@ export namespace gameCommands {
 
   export const newGame = async (): Promise<void> => await tryInvoke("new_game");
   export const getGame = async (): Promise<Game> => await tryInvoke("get_game");
-  export const nextTurn = async (): Promise<void> => await tryInvoke("next_turn");
+  export const nextTurn = async (): Promise<TurnChange> => await tryInvoke("next_turn");
   export const undo = async (): Promise<void> => await tryInvoke("undo");
   export const redo = async (): Promise<void> => await tryInvoke("redo");
-  export const damage = async (args: DamageArgs): Promise<void> => await tryInvoke("damage", args);
+  export const damage = async (args: DamageArgs): Promise<ConcentrationCheck | null> =>
+    await tryInvoke("damage", args);
+  export const breakConcentration = async (args: { target: number }): Promise<ExpiredCondition[]> =>
+    await tryInvoke("break_concentration", args);
   export const heal = async (args: HealArgs): Promise<void> => await tryInvoke("heal", args);
+  export const recordDeathSave = async (args: DeathSaveArgs): Promise<void> =>
+    await tryInvoke("record_death_save", args);
   export const setAction = async (args: SetActionArgs): Promise<void> => await tryInvoke("set_action", args);
   export const addConditions = async (args: AddConditionsArgs): Promise<void> =>
     await tryInvoke("add_conditions", args);
+  export const removeConditions = async (args: AddConditionsArgs): Promise<void> =>
+    await tryInvoke("remove_conditions", args);
 }
 
 export namespace diceCommands {
   export interface RollArgs {
     [key: string]: unknown;
     expr: string;
+    label?: string;
+    roller?: string;
+  }
+
+  export interface ReplayRollArgs {
+    [key: string]: unknown;
+    id: number;
+  }
+
+  export interface RollForArgs {
+    [key: string]: unknown;
+    participant: number;
+    expr: string;
+    label?: string;
+  }
+
+  export interface NotesArgs {
+    [key: string]: unknown;
+    participant: number;
+  }
+
+  export interface RollNoteArgs {
+    [key: string]: unknown;
+    participant: number;
+    index: number;
+  }
+
+  export interface RollLogArgs {
+    [key: string]: unknown;
+    filter?: RollLogFilter;
+    offset: number;
+    limit: number;
+  }
+
+  export interface ChanceArgs {
+    [key: string]: unknown;
+    expr: string;
+    target: number;
+    damage?: string;
   }
 
-  export const roll = async (args: RollArgs): Promise<Roll> => await tryInvoke("roll", args);
+  export const roll = async (args: RollArgs): Promise<Roll[]> => await tryInvoke("roll", args);
+  export const rollFor = async (args: RollForArgs): Promise<Roll[]> => await tryInvoke("roll_for", args);
+  export const rollCritical = async (args: RollArgs): Promise<Roll[]> => await tryInvoke("roll_critical", args);
+  export const getNotes = async (args: NotesArgs): Promise<Notes> => await tryInvoke("get_notes", args);
+  export const rollNote = async (args: RollNoteArgs): Promise<Roll[]> => await tryInvoke("roll_note", args);
+  export const replayRoll = async (args: ReplayRollArgs): Promise<Roll> => await tryInvoke("replay_roll", args);
+  export const checkDice = async (args: RollArgs): Promise<DiceCheck> => await tryInvoke("check_dice", args);
+  export const distribution = async (args: RollArgs): Promise<Distribution> =>
+    await tryInvoke("distribution", args);
+  export const chance = async (args: ChanceArgs): Promise<Chance> => await tryInvoke("chance", args);
+  export const getRollLog = async (args: RollLogArgs): Promise<RollLogRecord[]> =>
+    await tryInvoke("get_roll_log", args);
+  export const clearRollLog = async (): Promise<void> => await tryInvoke("clear_roll_log");
 }
 
 async function tryInvoke(command: string, args?: InvokeArgs): Promise<any> {
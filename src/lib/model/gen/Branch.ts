@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A branch taken during evaluation by a conditional or an attack.
+ */
+export type Branch = "then" | "else" | "hit" | "miss" | "critical";
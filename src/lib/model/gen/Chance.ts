@@ -0,0 +1,20 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The odds of a roll meeting or beating a target such as an armor class or a
+ * saving throw DC.
+ */
+export type Chance = { 
+/**
+ * The probability that the roll meets or beats the target.
+ */
+probability: number, 
+/**
+ * The expected damage of the attack, weighted by the probability of
+ * hitting, if a damage expression was given.
+ */
+expectedDamage: number | null, 
+/**
+ * Whether the results are exact or were estimated by sampling.
+ */
+exact: boolean, };
@@ -0,0 +1,30 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ExpiredCondition } from "./ExpiredCondition";
+import type { ParticipantId } from "./ParticipantId";
+import type { Roll } from "./Roll";
+
+/**
+ * The Constitution saving throw a concentrating participant must make on taking damage.
+ */
+export type ConcentrationCheck = { 
+/**
+ * The participant who took the damage.
+ */
+participant: ParticipantId, 
+/**
+ * The DC of the saving throw.
+ */
+dc: number, 
+/**
+ * The saving throw, if it was rolled automatically. A participant left incapacitated by the damage makes no save.
+ */
+roll: Roll | null, 
+/**
+ * Whether concentration was broken, by a failed save or by the participant being incapacitated. A save that was
+ * not rolled leaves concentration to be broken by hand.
+ */
+broken: boolean, 
+/**
+ * The conditions that ended with the participant's concentration.
+ */
+ended: Array<ExpiredCondition>, };
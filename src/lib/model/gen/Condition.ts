@@ -22,4 +22,14 @@ expiry: Expiry,
 /**
  * The participant who caused the condition, if it was not self-inflicted.
  */
-instigator: ParticipantId | null, };
+instigator: ParticipantId | null, 
+/**
+ * The names of the conditions that imply this one, if it was applied as an implication of them rather than
+ * directly. An implied condition is removed along with the last of the conditions that imply it.
+ */
+impliedBy?: Array<string>, 
+/**
+ * Whether the condition lasts only as long as its instigator concentrates, such as the effect of a concentration
+ * spell. It ends when the instigator's concentration is broken.
+ */
+concentration?: boolean, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The outcome of a single death saving throw, or of taking damage at 0 hit points.
+ */
+export type DeathSave = { "type": "success" } | { "type": "failure" } | { "type": "criticalSuccess" } | { "type": "criticalFailure" };
@@ -0,0 +1,26 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DeathSave } from "./DeathSave";
+import type { DeathSaves } from "./DeathSaves";
+import type { ParticipantId } from "./ParticipantId";
+import type { Roll } from "./Roll";
+
+/**
+ * A death saving throw rolled automatically at the start of a player's turn.
+ */
+export type DeathSaveRoll = { 
+/**
+ * The player who made the save.
+ */
+participant: ParticipantId, 
+/**
+ * The d20 roll.
+ */
+roll: Roll, 
+/**
+ * The outcome of the roll.
+ */
+save: DeathSave, 
+/**
+ * The player's saves after the roll.
+ */
+saves: DeathSaves, };
@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The death saving throws made by a player since they last dropped to 0 hit points or became stable.
+ */
+export type DeathSaves = { 
+/**
+ * Number of successful saves.
+ */
+successes: number, 
+/**
+ * Number of failed saves.
+ */
+failures: number, 
+/**
+ * Whether the player has stabilized and no longer makes death saves.
+ */
+stable: boolean, 
+/**
+ * Whether the player was reduced to 0 hit points by damage and makes death saves until they stabilize, die or
+ * regain hit points.
+ */
+dying: boolean, };
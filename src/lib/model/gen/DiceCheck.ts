@@ -0,0 +1,17 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DiceSyntaxError } from "./DiceSyntaxError";
+import type { DiceToken } from "./DiceToken";
+
+/**
+ * The tokens of a dice expression for syntax highlighting, and the first
+ * syntax error in it, if any.
+ */
+export type DiceCheck = { 
+/**
+ * The tokens of the expression, including any invalid input.
+ */
+tokens: Array<DiceToken>, 
+/**
+ * The first syntax error in the expression, if it is invalid.
+ */
+error: DiceSyntaxError | null, };
@@ -0,0 +1,30 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DiceSyntaxErrorKind } from "./DiceSyntaxErrorKind";
+import type { Span } from "./Span";
+
+/**
+ * A parsing error, with its location in the input so that it can be pointed
+ * out to the user.
+ */
+export type DiceSyntaxError = { 
+/**
+ * The kind of error.
+ */
+kind: DiceSyntaxErrorKind, 
+/**
+ * A description of the error.
+ */
+message: string, 
+/**
+ * The location of the offending input, which is empty at the end of the
+ * input.
+ */
+span: Span, 
+/**
+ * The tokens that would have been valid at the location of the error.
+ */
+expected: Array<string>, 
+/**
+ * Input that may have been intended in place of the offending input.
+ */
+suggestion: string | null, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Kinds of parsing errors.
+ */
+export type DiceSyntaxErrorKind = "unexpectedToken" | "unexpectedEnd" | "invalidDie" | "mismatchedParentheses" | "invalidRepeat" | "invalidCall" | "invalidCharacter" | "invalidWord" | "invalidInteger" | "tooDeep" | "impossibleTarget";
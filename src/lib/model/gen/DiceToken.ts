@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DiceTokenKind } from "./DiceTokenKind";
+import type { Span } from "./Span";
+
+/**
+ * A token's kind and location, used to highlight the syntax of an expression.
+ */
+export type DiceToken = { 
+/**
+ * The kind of token.
+ */
+kind: DiceTokenKind, 
+/**
+ * The location of the token in the input.
+ */
+span: Span, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Broad categories of tokens, used to highlight the syntax of an expression.
+ */
+export type DiceTokenKind = "integer" | "word" | "function" | "variable" | "label" | "operator" | "comparison" | "punctuation" | "error";
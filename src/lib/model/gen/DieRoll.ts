@@ -1,13 +1,18 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Outcome } from "./Outcome";
 
 /**
  * A single die roll.
  */
 export type DieRoll = { 
 /**
- * The number of sides on the die. May be 4, 6, 8, 10, 12, 20, or 100.
+ * The number of sides on the die. Fudge dice have three sides.
  */
 sides: number, 
+/**
+ * Whether the die is a Fudge die with the faces -1, 0 and +1.
+ */
+fudge: boolean, 
 /**
  * The result of the roll.
  */
@@ -15,4 +20,14 @@ result: number,
 /**
  * Whether the roll was kept or discarded during a selection operation.
  */
-keep: boolean, };
+keep: boolean, 
+/**
+ * Whether the die was rolled because another die exploded or, for
+ * compounding explosions, whether the result includes additional rolls.
+ */
+exploded: boolean, 
+/**
+ * Whether the die counted as a success or failure when counting
+ * successes, or `None` if it was not counted either way.
+ */
+outcome: Outcome | null, };
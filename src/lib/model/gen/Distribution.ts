@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Probability } from "./Probability";
+
+/**
+ * The probability distribution of the results of a dice expression.
+ */
+export type Distribution = { 
+/**
+ * Each possible result and its probability, from lowest to highest.
+ */
+outcomes: Array<Probability>, 
+/**
+ * Whether the probabilities are exact or were estimated by sampling
+ * because the expression is too complex to analyze exactly.
+ */
+exact: boolean, };
@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Condition } from "./Condition";
+import type { ParticipantId } from "./ParticipantId";
+
+/**
+ * A condition that has expired and the participant it was removed from.
+ */
+export type ExpiredCondition = { 
+/**
+ * The participant who had the condition.
+ */
+participant: ParticipantId, 
+/**
+ * The condition that expired.
+ */
+condition: Condition, };
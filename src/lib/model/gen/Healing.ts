@@ -3,4 +3,4 @@
 /**
  * Represents healing that can be applied to a participant.
  */
-export type Healing = { "type": "heal", amount: number, } | { "type": "setHp", amount: number, } | { "type": "setTempHp", amount: number, };
+export type Healing = { "type": "heal", amount: number, } | { "type": "setHp", amount: number, } | { "type": "setTempHp", amount: number, } | { "type": "setMaxHp", amount: number, };
@@ -0,0 +1,25 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DiceSyntaxError } from "./DiceSyntaxError";
+import type { Span } from "./Span";
+
+/**
+ * A dice expression embedded in notes, such as `[[d20 + 5|Bite]]`.
+ */
+export type InlineRoll = { 
+/**
+ * The location of the markup in the notes, including the brackets.
+ */
+span: Span, 
+/**
+ * The dice expression, without surrounding whitespace.
+ */
+expression: string, 
+/**
+ * The label following the expression, if any.
+ */
+label: string | null, 
+/**
+ * The syntax error in the expression, if it is invalid, located within
+ * the notes rather than the expression.
+ */
+error: DiceSyntaxError | null, };
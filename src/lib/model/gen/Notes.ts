@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { InlineRoll } from "./InlineRoll";
+
+/**
+ * Free-form notes and the inline rolls found in them.
+ */
+export type Notes = { 
+/**
+ * The text of the notes, including the markup of each inline roll.
+ */
+text: string, 
+/**
+ * The inline rolls in the notes, in order.
+ */
+rolls: Array<InlineRoll>, };
@@ -1,6 +1,7 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { Class } from "./Class";
 import type { Condition } from "./Condition";
+import type { DeathSaves } from "./DeathSaves";
 import type { Stats } from "./Stats";
 
 /**
@@ -46,6 +47,23 @@ initiative: number,
  * Tiebreaker value for initiative ties.
  */
 tiebreaker: number, 
+/**
+ * Current hit points.
+ */
+hp: number, 
+/**
+ * Current temporary hit points.
+ */
+tempHp: number, 
+/**
+ * Maximum hit points, or 0 if not yet known, in which case healing is not capped and the player is never
+ * bloodied.
+ */
+maxHp: number, 
+/**
+ * Death saving throws made since the player last dropped to 0 hit points.
+ */
+deathSaves: DeathSaves, 
 /**
  * Whether the player's standard action is available.
  */
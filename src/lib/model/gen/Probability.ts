@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The probability of a single result of a dice expression.
+ */
+export type Probability = { 
+/**
+ * The result.
+ */
+value: number, 
+/**
+ * The probability of the result, between 0 and 1.
+ */
+probability: number, };
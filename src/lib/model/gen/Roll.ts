@@ -1,4 +1,40 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Branch } from "./Branch";
 import type { DieRoll } from "./DieRoll";
+import type { RollStep } from "./RollStep";
 
-export type Roll = { value: number, dice: Array<DieRoll>, };
+export type Roll = { value: number, dice: Array<DieRoll>, 
+/**
+ * The subtotal of each label in the expression, such as `fire` in
+ * `2d6[fire] + 3`.
+ */
+breakdown: { [key in string]: number }, 
+/**
+ * The branches taken by conditionals and attacks in the expression, in
+ * the order they were evaluated.
+ */
+branches: Array<Branch>, 
+/**
+ * The value of each variable in the expression, such as `dex` in
+ * `d20 + @dex`, so that the roll can be replayed after it changes.
+ */
+variables: { [key in string]: number }, 
+/**
+ * A step-by-step explanation of the roll, such as
+ * `4d6kh3 → [6, 5, 3, ~1~] = 14; 14 + 2 = 16`.
+ */
+explanation: string, 
+/**
+ * The steps of the explanation: each roll with its dice and total,
+ * followed by the arithmetic that combines them.
+ */
+trace: Array<RollStep>, 
+/**
+ * The seed of the random number generator used for the roll.
+ */
+seed: number, 
+/**
+ * The stream of the random number generator used for the roll. Rolling
+ * the same expression with the same seed and stream replays the roll.
+ */
+stream: number, };
@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Criteria for selecting rolls from the roll log. Unset criteria match any
+ * roll.
+ */
+export type RollLogFilter = { 
+/**
+ * Only rolls made by this roller.
+ */
+roller: string | null, 
+/**
+ * Only rolls with this label.
+ */
+label: string | null, 
+/**
+ * Only rolls whose expression contains this text (ignoring case).
+ */
+expression: string | null, };
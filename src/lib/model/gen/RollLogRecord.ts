@@ -0,0 +1,63 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DieRoll } from "./DieRoll";
+
+/**
+ * A dice roll recorded in the roll log.
+ */
+export type RollLogRecord = { 
+/**
+ * Database ID.
+ */
+id: number, 
+/**
+ * When the roll was made (RFC 3339 format).
+ */
+timestamp: string, 
+/**
+ * The dice expression that was rolled.
+ */
+expression: string, 
+/**
+ * The result of the roll.
+ */
+value: number, 
+/**
+ * The individual dice rolled.
+ */
+dice: Array<DieRoll>, 
+/**
+ * A step-by-step explanation of the roll.
+ */
+explanation: string, 
+/**
+ * The seed of the random number generator used for the roll.
+ */
+seed: number, 
+/**
+ * The stream of the random number generator used for the roll.
+ */
+stream: number, 
+/**
+ * What the roll was for, e.g. "Attack".
+ */
+label: string | null, 
+/**
+ * Who made the roll, e.g. the name of a participant.
+ */
+roller: string | null, 
+/**
+ * Whether the roll was made as a critical hit.
+ */
+critical: boolean, 
+/**
+ * The campaign's rule for critical hits when the roll was made.
+ */
+criticalRule: "double_dice" | "max_plus_roll", 
+/**
+ * The ID of the participant the roll was made for, if any.
+ */
+participant: number | null, 
+/**
+ * The value of each variable the roll used.
+ */
+variables: { [key in string]: number }, };
@@ -0,0 +1,22 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DieRoll } from "./DieRoll";
+
+/**
+ * A step in the explanation of a roll: either a roll of dice and the total
+ * they contributed, or the arithmetic that combines the totals of earlier
+ * steps into the result.
+ */
+export type RollStep = { 
+/**
+ * The part of the expression evaluated by this step, with the totals of
+ * earlier steps in place of their rolls.
+ */
+expression: string, 
+/**
+ * The dice rolled by this step, including any that were discarded.
+ */
+dice: Array<DieRoll>, 
+/**
+ * The result of this step.
+ */
+value: number, };
@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The range of byte offsets occupied by a token or error in the input. Spans
+ * sent to the frontend are first converted to UTF-16 code units with
+ * [`Span::to_utf16`], since JavaScript indexes strings in those.
+ */
+export type Span = { 
+/**
+ * The offset of the first byte.
+ */
+start: number, 
+/**
+ * The offset one past the last byte.
+ */
+end: number, };
@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DeathSaveRoll } from "./DeathSaveRoll";
+import type { ExpiredCondition } from "./ExpiredCondition";
+
+/**
+ * What happened as play passed to the next turn, so that it can be announced.
+ */
+export type TurnChange = { 
+/**
+ * The conditions that expired as the previous turn ended or the next one started.
+ */
+expired: Array<ExpiredCondition>, 
+/**
+ * The death save rolled for the player whose turn started, if they are dying and saves are rolled.
+ */
+deathSave: DeathSaveRoll | null, };
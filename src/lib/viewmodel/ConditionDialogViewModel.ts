@@ -16,6 +16,7 @@ export interface ConditionBuildOptions {
   elapsedDuration: number;
   elapsedUnit: ElapsedUnit;
   instigatorId: number | null;
+  isConcentrating: boolean;
 }
 
 export class ConditionDialogViewModel {
@@ -42,8 +43,15 @@ export class ConditionDialogViewModel {
   }
 
   buildConditions(options: ConditionBuildOptions): Condition[] {
-    const { selectedConditions, customConditionName, durationType, elapsedDuration, elapsedUnit, instigatorId } =
-      options;
+    const {
+      selectedConditions,
+      customConditionName,
+      durationType,
+      elapsedDuration,
+      elapsedUnit,
+      instigatorId,
+      isConcentrating,
+    } = options;
 
     const trimmedCustom = customConditionName.trim();
     const baseConditions = selectedConditions.filter((condition) => condition !== "other");
@@ -58,6 +66,7 @@ export class ConditionDialogViewModel {
       startTime,
       expiry,
       instigator: instigatorId ?? undefined,
+      concentration: isConcentrating && instigatorId !== null,
     }));
   }
 
@@ -6,6 +6,7 @@ import { type Roll } from "$lib/model/gen/Roll";
 
 export type HistoryItem = {
   expression: string;
+  index?: number;
   roll?: Roll;
   error?: string;
 };
@@ -13,11 +14,16 @@ export type HistoryItem = {
 export class DiceViewModel {
   public history: HistoryItem[] = [];
 
-  async roll(expr: string): Promise<Roll> {
+  async roll(expr: string): Promise<Roll[]> {
     try {
-      const roll = await diceCommands.roll({ expr });
-      this.history = [...this.history, { expression: expr, roll }];
-      return roll;
+      const rolls = await diceCommands.roll({ expr });
+      const items = rolls.map((roll, i) => ({
+        expression: expr,
+        index: rolls.length > 1 ? i + 1 : undefined,
+        roll,
+      }));
+      this.history = [...this.history, ...items];
+      return rolls;
     } catch (e) {
       this.history = [...this.history, { expression: expr, error: `${e}` }];
       throw e;
@@ -3,6 +3,7 @@
 
 import { gameCommands } from "$lib/model/Commands";
 import type { Game } from "$lib/model/Game";
+import type { TurnChange } from "$lib/model/gen/TurnChange";
 import type { ParticipantViewModel } from "./ParticipantViewModel";
 import { createParticipantViewModel } from "./ParticipantViewModelFactory";
 
@@ -42,8 +43,8 @@ export class GameViewModel {
     );
   }
 
-  async nextTurn(): Promise<void> {
-    await gameCommands.nextTurn();
+  async nextTurn(): Promise<TurnChange> {
+    return await gameCommands.nextTurn();
   }
 
   async undo(): Promise<void> {
@@ -4,6 +4,7 @@
 import { gameCommands } from "$lib/model/Commands";
 import type { Condition } from "$lib/model/Condition";
 import type { Damage, Healing } from "$lib/model/Damage";
+import type { ConcentrationCheck } from "$lib/model/gen/ConcentrationCheck";
 import type { Action, Monster } from "$lib/model/Participant";
 import type { Stats } from "$lib/model/Stats";
 import { ParticipantViewModel, conditionPriorities } from "./ParticipantViewModel";
@@ -111,8 +112,8 @@ export class MonsterViewModel extends ParticipantViewModel {
     });
   }
 
-  async damage(damage: Damage): Promise<void> {
-    await gameCommands.damage({
+  async damage(damage: Damage): Promise<ConcentrationCheck | null> {
+    return await gameCommands.damage({
       target: this._id,
       damage
     });
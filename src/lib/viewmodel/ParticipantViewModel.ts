@@ -3,6 +3,7 @@
 
 import { gameCommands } from "$lib/model/Commands";
 import type { Condition } from "$lib/model/gen/Condition";
+import type { ExpiredCondition } from "$lib/model/gen/ExpiredCondition";
 import type { Participant } from "$lib/model/gen/Participant";
 
 export abstract class ParticipantViewModel {
@@ -20,6 +21,17 @@ export abstract class ParticipantViewModel {
       conditions
     });
   }
+
+  async removeConditions(conditions: Condition[]): Promise<void> {
+    await gameCommands.removeConditions({
+      target: this.id,
+      conditions
+    });
+  }
+
+  async breakConcentration(): Promise<ExpiredCondition[]> {
+    return await gameCommands.breakConcentration({ target: this.id });
+  }
 }
 
 export const conditionPriorities: Record<Condition["name"], number> = {
@@ -3,6 +3,10 @@
 
 import { gameCommands } from "$lib/model/Commands";
 import type { Condition } from "$lib/model/Condition";
+import type { Damage, Healing } from "$lib/model/Damage";
+import type { ConcentrationCheck } from "$lib/model/gen/ConcentrationCheck";
+import type { DeathSave } from "$lib/model/gen/DeathSave";
+import type { DeathSaves } from "$lib/model/gen/DeathSaves";
 import type { Action, Player } from "$lib/model/Participant";
 import type { Stats } from "$lib/model/Stats";
 import { ParticipantViewModel, conditionPriorities } from "./ParticipantViewModel";
@@ -42,6 +46,22 @@ export class PlayerViewModel extends ParticipantViewModel {
     );
   }
 
+  get hp(): number {
+    return this._model.hp;
+  }
+
+  get maxHp(): number {
+    return this._model.maxHp;
+  }
+
+  get tempHp(): number {
+    return this._model.tempHp;
+  }
+
+  get deathSaves(): DeathSaves {
+    return this._model.deathSaves;
+  }
+
   get stats(): Stats {
     return this._model.stats;
   }
@@ -80,4 +100,25 @@ export class PlayerViewModel extends ParticipantViewModel {
       available
     });
   }
+
+  async damage(damage: Damage): Promise<ConcentrationCheck | null> {
+    return await gameCommands.damage({
+      target: this._id,
+      damage
+    });
+  }
+
+  async recordDeathSave(save: DeathSave): Promise<void> {
+    await gameCommands.recordDeathSave({
+      target: this._id,
+      save
+    });
+  }
+
+  async heal(healing: Healing): Promise<void> {
+    await gameCommands.heal({
+      target: this._id,
+      healing
+    });
+  }
 }